@@ -0,0 +1,54 @@
+#![feature(custom_test_frameworks)]
+#![test_runner(criterion::runner)]
+
+use arrayvec::ArrayVec;
+use cinder::chess::{Move, Position};
+use criterion::{Criterion, Throughput};
+use criterion_macro::criterion;
+
+fn copy_make(pos: &Position, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let mut nodes = 0;
+    let moves = ArrayVec::<Move, 256>::from_iter(pos.moves().flatten());
+    for m in moves {
+        let mut next = pos.clone();
+        next.play(m);
+        nodes += copy_make(&next, depth - 1);
+    }
+
+    nodes
+}
+
+fn make_unmake(pos: &mut Position, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let mut nodes = 0;
+    let moves = ArrayVec::<Move, 256>::from_iter(pos.moves().flatten());
+    for m in moves {
+        let undo = pos.make(m);
+        nodes += make_unmake(pos, depth - 1);
+        pos.undo(undo);
+    }
+
+    nodes
+}
+
+#[criterion]
+fn crit(c: &mut Criterion) {
+    let depth = 4;
+    let pos = Position::default();
+    let nodes = copy_make(&pos, depth);
+
+    c.benchmark_group("walk")
+        .throughput(Throughput::Elements(nodes))
+        .bench_function("copy-make", |b| b.iter(|| copy_make(&pos, depth)))
+        .bench_function("make-unmake", |b| {
+            let mut pos = pos.clone();
+            b.iter(|| make_unmake(&mut pos, depth))
+        });
+}
@@ -1,21 +1,43 @@
 use cinder::uci::Uci;
-use futures::{channel::mpsc::unbounded, executor::block_on, sink::unfold as sink};
+use futures::{channel::mpsc::unbounded, executor::block_on, sink::unfold as sink, stream};
 use std::io::{prelude::*, stdin, stdout};
-use std::{future::ready, thread};
+use std::{env, future::ready, process::ExitCode, thread};
 
-fn main() {
-    let (tx, input) = unbounded();
+fn main() -> ExitCode {
+    let args: Vec<_> = env::args().skip(1).collect();
+    let mut stdout = stdout().lock();
+    let output = sink((), |_, line: String| ready(writeln!(stdout, "{line}")));
+
+    match args.first().map(String::as_str) {
+        None => {
+            let (tx, input) = unbounded();
+            thread::spawn(move || {
+                let mut lines = stdin().lock().lines();
+                while let Some(Ok(line)) = lines.next() {
+                    if tx.unbounded_send(line).is_err() {
+                        break;
+                    }
+                }
+            });
+
+            block_on(Uci::new(input, output).run()).unwrap();
+            ExitCode::SUCCESS
+        }
 
-    thread::spawn(move || {
-        let mut lines = stdin().lock().lines();
-        while let Some(Ok(line)) = lines.next() {
-            if tx.unbounded_send(line).is_err() {
-                break;
+        Some("bench") => {
+            let mut uci = Uci::new(stream::empty::<String>(), output);
+            match block_on(uci.execute(&args.join(" "))) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    eprintln!("{e}");
+                    ExitCode::FAILURE
+                }
             }
         }
-    });
 
-    let mut stdout = stdout().lock();
-    let output = sink((), |_, line: String| ready(writeln!(stdout, "{line}")));
-    block_on(Uci::new(input, output).run()).unwrap();
+        Some(_) => {
+            eprintln!("usage: cinder [bench [depth] [threads] [hash]]");
+            ExitCode::FAILURE
+        }
+    }
 }
@@ -0,0 +1,54 @@
+use cinder::chess::Color;
+use cinder::nnue::Evaluator;
+use cinder::search::{Engine, Limits, Score};
+use cinder::util::Trigger;
+use rand::{Rng, seq::IteratorRandom};
+
+/// Games that last longer than this many plies are adjudicated as draws.
+pub const MAX_PLIES: usize = 400;
+
+/// Plays a few random moves from the starting position.
+pub fn opening<R: Rng>(rng: &mut R, plies: usize) -> Evaluator {
+    loop {
+        let mut pos = Evaluator::default();
+        for _ in 0..plies {
+            match pos.moves().flatten().choose(rng) {
+                Some(m) => pos.play(m),
+                None => break,
+            }
+        }
+
+        if pos.outcome().is_none() {
+            return pos;
+        }
+    }
+}
+
+/// Plays a game searching a fixed number of nodes per move and returns the winner, if any.
+///
+/// Every position is reported to `observe` along with the score of its search.
+pub fn play(
+    mut pos: Evaluator,
+    [white, black]: [&Engine; 2],
+    nodes: u64,
+    mut observe: impl FnMut(&Evaluator, Score),
+) -> Option<Color> {
+    let limits = Limits::Nodes(nodes);
+    for _ in 0..MAX_PLIES {
+        if let Some(outcome) = pos.outcome() {
+            return outcome.winner();
+        }
+
+        let engine = match pos.turn() {
+            Color::White => white,
+            Color::Black => black,
+        };
+
+        let stopper = Trigger::armed();
+        let result = engine.search(&pos, &limits, &[], &Trigger::disarmed(), &stopper, |_| {});
+        observe(&pos, result.score());
+        pos.play(result.head()?);
+    }
+
+    None
+}
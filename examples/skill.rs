@@ -0,0 +1,380 @@
+//! Rates every [`SkillLevel`] below full strength in a gauntlet.
+//!
+//! Usage:
+//! * `cargo run --release --example skill -- play <openings> <seconds>+<increment> <results.csv> [<engine>=<rating>...]`
+//! * `cargo run --release --example skill -- fit <results.csv> [<engine>=<rating>...]`
+//!
+//! Every skill level plays the level right above it, as well as every reference engine,
+//! from the same random openings with colors reversed at the given time control,
+//! and the outcome of every game is appended to the results.
+//! Reference engines are UCI engines rated on an external scale, such as [CCRL]'s,
+//! whose ratings are held fixed while those of the skill levels are fitted by maximum likelihood.
+//! Without reference engines, the scale is anchored by pinning the lowest level to [`Elo::MIN`].
+//!
+//! [`SkillLevel`]: cinder::search::SkillLevel
+//! [`Elo::MIN`]: cinder::search::Elo
+//! [CCRL]: https://computerchess.org.uk/ccrl/
+
+#[allow(dead_code)] // only part of the module is shared with this example
+mod selfplay;
+
+use cinder::chess::{Color, Move};
+use cinder::nnue::Evaluator;
+use cinder::search::{Elo, Engine, Limits, Options, SkillLevel};
+use cinder::util::{Integer, Trigger};
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+use std::io::{self, BufRead, BufReader, BufWriter, ErrorKind, Lines, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::time::{Duration, Instant};
+use std::{collections::HashMap, env, fs::File, fs::OpenOptions, iter};
+
+/// A reference engine that speaks UCI.
+struct Reference {
+    process: Child,
+    input: ChildStdin,
+    output: Lines<BufReader<ChildStdout>>,
+}
+
+impl Reference {
+    fn spawn(path: &str) -> io::Result<Self> {
+        let mut process = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+
+        let input = process.stdin.take().ok_or(ErrorKind::BrokenPipe)?;
+        let output = process.stdout.take().ok_or(ErrorKind::BrokenPipe)?;
+        let output = BufReader::new(output).lines();
+        let mut reference = Reference {
+            process,
+            input,
+            output,
+        };
+
+        reference.send("uci")?;
+        reference.receive("uciok")?;
+        Ok(reference)
+    }
+
+    fn send(&mut self, command: &str) -> io::Result<()> {
+        writeln!(self.input, "{command}")?;
+        self.input.flush()
+    }
+
+    /// Reads lines until one starts with `prefix` and returns it.
+    fn receive(&mut self, prefix: &str) -> io::Result<String> {
+        for line in &mut self.output {
+            let line = line?;
+            if line.starts_with(prefix) {
+                return Ok(line);
+            }
+        }
+
+        Err(ErrorKind::UnexpectedEof.into())
+    }
+
+    fn new_game(&mut self) -> io::Result<()> {
+        self.send("ucinewgame")?;
+        self.send("isready")?;
+        self.receive("readyok")?;
+        Ok(())
+    }
+
+    /// Searches the position reached from `fen` after `moves` and returns the best move.
+    fn search(
+        &mut self,
+        fen: &str,
+        moves: &[Move],
+        clocks: [Duration; 2],
+        inc: Duration,
+    ) -> io::Result<String> {
+        let moves = Vec::from_iter(moves.iter().map(Move::to_string));
+        self.send(&format!("position fen {fen} moves {}", moves.join(" ")))?;
+        self.send(&format!(
+            "go wtime {} btime {} winc {} binc {}",
+            clocks[0].as_millis(),
+            clocks[1].as_millis(),
+            inc.as_millis(),
+            inc.as_millis(),
+        ))?;
+
+        let line = self.receive("bestmove")?;
+        let best = line.split_whitespace().nth(1);
+        Ok(best.ok_or(ErrorKind::InvalidData)?.to_string())
+    }
+}
+
+impl Drop for Reference {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let _ = self.process.wait();
+    }
+}
+
+/// A participant in the gauntlet.
+enum Player {
+    Level(Box<Engine>),
+    Reference(Reference),
+}
+
+impl Player {
+    fn new_game(&mut self) -> io::Result<()> {
+        match self {
+            Player::Level(engine) => engine.clear(),
+            Player::Reference(reference) => reference.new_game()?,
+        }
+
+        Ok(())
+    }
+}
+
+/// Plays a game at a time control of `time` plus `inc` per move and returns the winner, if any.
+///
+/// A player that runs out of time loses the game.
+fn play(
+    opening: &Evaluator,
+    players: [&mut Player; 2],
+    time: Duration,
+    inc: Duration,
+) -> io::Result<Option<Color>> {
+    let fen = opening.to_string();
+    let mut pos = opening.clone();
+    let mut moves = Vec::new();
+    let mut clocks = [time; 2];
+    let [white, black] = players;
+    white.new_game()?;
+    black.new_game()?;
+
+    for _ in 0..selfplay::MAX_PLIES {
+        if let Some(outcome) = pos.outcome() {
+            return Ok(outcome.winner());
+        }
+
+        let (player, side) = match pos.turn() {
+            Color::White => (&mut *white, 0),
+            Color::Black => (&mut *black, 1),
+        };
+
+        let timer = Instant::now();
+        let best = match player {
+            Player::Level(engine) => {
+                let limits = Limits::Clock(clocks[side], inc, None, Some(clocks[1 - side]));
+                let stopper = Trigger::armed();
+                let result =
+                    engine.search(&pos, &limits, &[], &Trigger::disarmed(), &stopper, |_| {});
+                result.head()
+            }
+
+            Player::Reference(reference) => {
+                let best = reference.search(&fen, &moves, clocks, inc)?;
+                pos.moves().flatten().find(|m| m.to_string() == best)
+            }
+        };
+
+        let Some(clock) = clocks[side].checked_sub(timer.elapsed()) else {
+            return Ok(Some(!pos.turn()));
+        };
+
+        let Some(m) = best else {
+            return Err(io::Error::new(ErrorKind::InvalidData, "illegal move"));
+        };
+
+        clocks[side] = clock + inc;
+        moves.push(m);
+        pos.play(m);
+    }
+
+    Ok(None)
+}
+
+/// Plays every pairing of the gauntlet and appends the outcome of every game to the results.
+fn gauntlet(
+    openings: usize,
+    (time, inc): (Duration, Duration),
+    references: &[(String, f64)],
+    csv: &mut impl Write,
+) -> io::Result<()> {
+    let mut levels = Vec::from_iter((SkillLevel::MIN..SkillLevel::MAX).map(|l| {
+        Player::Level(Box::new(Engine::with_options(&Options {
+            skill: SkillLevel::new(l),
+            ..Options::default()
+        })))
+    }));
+
+    let mut refs = Vec::new();
+    for (path, _) in references {
+        refs.push(Player::Reference(Reference::spawn(path)?));
+    }
+
+    for level in SkillLevel::MIN..SkillLevel::MAX {
+        let (lower, upper) = levels.split_at_mut(level as usize + 1);
+        let player = &mut lower[level as usize];
+        let mut opponents = Vec::from_iter(upper.first_mut().map(|p| ((level + 1).to_string(), p)));
+        for ((path, _), reference) in references.iter().zip(&mut refs) {
+            opponents.push((path.clone(), reference));
+        }
+
+        for (name, opponent) in opponents {
+            let mut rng = Pcg64::seed_from_u64(level.into());
+            for _ in 0..openings {
+                let pos = selfplay::opening(&mut rng, 8);
+                for color in Color::iter() {
+                    let (white, black, players) = match color {
+                        Color::White => (
+                            level.to_string(),
+                            name.clone(),
+                            [&mut *player, &mut *opponent],
+                        ),
+                        Color::Black => (
+                            name.clone(),
+                            level.to_string(),
+                            [&mut *opponent, &mut *player],
+                        ),
+                    };
+
+                    let result = match play(&pos, players, time, inc)? {
+                        None => "1/2-1/2",
+                        Some(Color::White) => "1-0",
+                        Some(Color::Black) => "0-1",
+                    };
+
+                    writeln!(csv, "{white},{black},{result},{pos}")?;
+                    csv.flush()?;
+                    eprintln!("{white} vs {black}: {result}");
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The expected score of a player rated `a` against one rated `b`.
+fn expected(a: f64, b: f64) -> f64 {
+    1. / (1. + 10f64.powf((b - a) / 400.))
+}
+
+/// Makes the ratings non-decreasing by pooling adjacent violators into their average.
+fn isotonic(ratings: &[f64]) -> Vec<f64> {
+    let mut blocks: Vec<(f64, usize)> = Vec::new();
+    for &rating in ratings {
+        blocks.push((rating, 1));
+        while let [.., (a, n), (b, m)] = blocks[..] {
+            if a <= b {
+                break;
+            }
+
+            blocks.truncate(blocks.len() - 2);
+            blocks.push(((a * n as f64 + b * m as f64) / (n + m) as f64, n + m));
+        }
+    }
+
+    Vec::from_iter(blocks.into_iter().flat_map(|(r, n)| iter::repeat_n(r, n)))
+}
+
+/// Fits the ratings of the skill levels to the results by maximum likelihood.
+fn fit(csv: impl BufRead, references: &[(String, f64)]) -> io::Result<()> {
+    let mut ratings = HashMap::<String, f64>::from_iter(references.iter().cloned());
+    let levels = Vec::from_iter((SkillLevel::MIN..SkillLevel::MAX).map(|l| l.to_string()));
+    for level in &levels {
+        ratings.insert(level.clone(), Elo::MIN as f64);
+    }
+
+    // A virtual draw between adjacent levels keeps perfect scores from diverging.
+    let mut games = Vec::from_iter(levels.windows(2).map(|w| (w[0].clone(), w[1].clone(), 0.5)));
+    for line in csv.lines() {
+        let line = line?;
+        let fields = Vec::from_iter(line.splitn(4, ','));
+        let [white, black, result, _] = fields[..] else {
+            return Err(io::Error::new(ErrorKind::InvalidData, line));
+        };
+
+        let score = match result {
+            "1-0" => 1.,
+            "0-1" => 0.,
+            "1/2-1/2" => 0.5,
+            _ => return Err(io::Error::new(ErrorKind::InvalidData, line)),
+        };
+
+        if !ratings.contains_key(white) || !ratings.contains_key(black) {
+            return Err(io::Error::new(ErrorKind::NotFound, line));
+        }
+
+        games.push((white.to_string(), black.to_string(), score));
+    }
+
+    // Newton's method on the rating of one level at a time.
+    for _ in 0..1000 {
+        for level in &levels {
+            let (mut gradient, mut curvature) = (0., 0.);
+            for (white, black, score) in &games {
+                let (opponent, score) = match (white == level, black == level) {
+                    (true, false) => (black, *score),
+                    (false, true) => (white, 1. - score),
+                    _ => continue,
+                };
+
+                let p = expected(ratings[level], ratings[opponent]);
+                gradient += score - p;
+                curvature += p * (1. - p);
+            }
+
+            let step = 400. / 10f64.ln() * gradient / curvature.max(1E-9);
+            *ratings.get_mut(level).unwrap() += step.clamp(-100., 100.);
+        }
+    }
+
+    let ratings = Vec::from_iter(levels.iter().map(|l| ratings[l]));
+    let offset = match references {
+        [] => Elo::MIN as f64 - ratings[0],
+        _ => 0.,
+    };
+
+    let ratings = isotonic(&ratings);
+    let ratings = Vec::from_iter(ratings.iter().map(|r| (r + offset).round() as u16));
+    println!("RATINGS = {ratings:?}");
+    Ok(())
+}
+
+/// Parses reference engines given as `<engine>=<rating>`.
+fn references(args: &[&str]) -> io::Result<Vec<(String, f64)>> {
+    let mut references = Vec::new();
+    for arg in args {
+        let Some((path, rating)) = arg.rsplit_once('=') else {
+            return Err(io::Error::new(ErrorKind::InvalidInput, arg.to_string()));
+        };
+
+        references.push((path.to_string(), rating.parse().map_err(io::Error::other)?));
+    }
+
+    Ok(references)
+}
+
+fn main() -> io::Result<()> {
+    let args = Vec::from_iter(env::args().skip(1));
+    match &args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["play", openings, tc, path, refs @ ..] => {
+            let openings = openings.parse().map_err(io::Error::other)?;
+            let Some((time, inc)) = tc.split_once('+') else {
+                return Err(io::Error::new(ErrorKind::InvalidInput, tc.to_string()));
+            };
+
+            let time = Duration::from_secs_f64(time.parse().map_err(io::Error::other)?);
+            let inc = Duration::from_secs_f64(inc.parse().map_err(io::Error::other)?);
+            let csv = OpenOptions::new().create(true).append(true).open(path)?;
+            gauntlet(
+                openings,
+                (time, inc),
+                &references(refs)?,
+                &mut BufWriter::new(csv),
+            )
+        }
+
+        ["fit", path, refs @ ..] => fit(BufReader::new(File::open(path)?), &references(refs)?),
+        _ => Err(io::Error::other(
+            "usage: skill play <openings> <seconds>+<increment> <results.csv> [<engine>=<rating>...] \
+             | skill fit <results.csv> [<engine>=<rating>...]",
+        )),
+    }
+}
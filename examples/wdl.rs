@@ -0,0 +1,228 @@
+//! Fits the parameters of the [`WdlModel`] to games of self-play.
+//!
+//! Usage:
+//! * `cargo run --release --example wdl -- play <games> <nodes per move> <data.csv>`
+//! * `cargo run --release --example wdl -- fit <data.csv>`
+//!
+//! Games start from a few random moves and every position is recorded along with its material,
+//! the score of its search and the outcome of the game from the point of view of the side to move.
+//! The coefficients of the cubic polynomials of material are then fitted by maximum likelihood.
+//!
+//! [`WdlModel`]: cinder::search::WdlModel
+
+mod selfplay;
+
+use cinder::chess::{Position, Role};
+use cinder::search::Engine;
+use cinder::util::Integer;
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+use std::io::{self, BufRead, BufReader, BufWriter, ErrorKind, Write};
+use std::{collections::HashMap, env, fs::File};
+
+/// An outcome from the point of view of the side to move.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Wdl {
+    Win,
+    Draw,
+    Loss,
+}
+
+fn material(pos: &Position) -> u32 {
+    pos.iter()
+        .map(|(p, _)| match p.role() {
+            Role::Pawn => 1,
+            Role::Knight | Role::Bishop => 3,
+            Role::Rook => 5,
+            Role::Queen => 9,
+            Role::King => 0,
+        })
+        .sum::<u32>()
+        .clamp(17, 78)
+}
+
+fn sigmoid(x: f64) -> f64 {
+    1. / (1. + (-x).exp())
+}
+
+fn poly(c: &[f64], x: f64) -> f64 {
+    c.iter().fold(0., |acc, &c| acc * x + c)
+}
+
+/// The negative log-likelihood of the outcomes given the coefficients of `a` and `b`.
+fn nll(samples: &[(f64, f64, [f64; 3])], coefficients: [f64; 8]) -> f64 {
+    let (ca, cb) = coefficients.split_at(4);
+    let mut sum = 0.;
+    for &(m, s, counts) in samples {
+        let (a, b) = (poly(ca, m), poly(cb, m));
+        if b <= 0. {
+            return f64::INFINITY;
+        }
+
+        let win = sigmoid((s - a) / b);
+        let loss = sigmoid((-s - a) / b);
+        let draw = 1. - win - loss;
+        for (n, p) in counts.into_iter().zip([win, draw, loss]) {
+            sum -= n * p.max(1E-12).ln();
+        }
+    }
+
+    sum
+}
+
+/// Minimizes a function with the Nelder-Mead method.
+fn minimize<const N: usize>(f: impl Fn([f64; N]) -> f64, x: [f64; N], step: f64) -> [f64; N] {
+    let mut simplex = Vec::from_iter((0..=N).map(|i| {
+        let mut p = x;
+        if let Some(c) = p.get_mut(i) {
+            *c += step;
+        }
+
+        (f(p), p)
+    }));
+
+    for _ in 0..N * 2000 {
+        simplex.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        let (worst_f, worst) = simplex[N];
+        let mut centroid = [0.; N];
+        for (_, p) in &simplex[..N] {
+            for (c, x) in centroid.iter_mut().zip(p) {
+                *c += x / N as f64;
+            }
+        }
+
+        let towards = |k: f64| {
+            let mut p = centroid;
+            for (c, w) in p.iter_mut().zip(worst) {
+                *c += k * (w - *c);
+            }
+
+            p
+        };
+
+        let reflected = towards(-1.);
+        let reflected_f = f(reflected);
+        simplex[N] = if reflected_f < simplex[0].0 {
+            let expanded = towards(-2.);
+            let expanded_f = f(expanded);
+            match expanded_f < reflected_f {
+                true => (expanded_f, expanded),
+                false => (reflected_f, reflected),
+            }
+        } else if reflected_f < simplex[N - 1].0 {
+            (reflected_f, reflected)
+        } else {
+            let contracted = towards(0.5);
+            let contracted_f = f(contracted);
+            if contracted_f < worst_f {
+                (contracted_f, contracted)
+            } else {
+                let best = simplex[0].1;
+                for (v, p) in &mut simplex[1..] {
+                    for (c, b) in p.iter_mut().zip(best) {
+                        *c = (*c + b) / 2.;
+                    }
+
+                    *v = f(*p);
+                }
+
+                continue;
+            }
+        };
+    }
+
+    simplex.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    simplex[0].1
+}
+
+/// Plays games of self-play and records every position.
+fn play(games: usize, nodes: u64, csv: &mut impl Write) -> io::Result<()> {
+    let mut rng = Pcg64::seed_from_u64(0);
+    let mut engine = Engine::new();
+    for game in 0..games {
+        let mut positions = Vec::new();
+        let pos = selfplay::opening(&mut rng, 8);
+        let winner = selfplay::play(pos, [&engine; 2], nodes, |pos, score| {
+            if score.mate().is_none() {
+                positions.push((material(pos), score.get(), pos.turn()));
+            }
+        });
+
+        for (material, score, turn) in positions {
+            let wdl = match winner {
+                None => Wdl::Draw,
+                Some(c) if c == turn => Wdl::Win,
+                Some(_) => Wdl::Loss,
+            };
+
+            writeln!(csv, "{material},{score},{wdl:?}")?;
+        }
+
+        engine.clear();
+        eprintln!("game {}/{games}: {winner:?}", game + 1);
+    }
+
+    Ok(())
+}
+
+/// Fits the coefficients of `a` and `b` to the positions recorded.
+fn fit(csv: impl BufRead) -> io::Result<()> {
+    let mut counts = HashMap::<_, [f64; 3]>::new();
+    for line in csv.lines() {
+        let line = line?;
+        let fields = Vec::from_iter(line.split(','));
+        let [material, score, wdl] = fields[..] else {
+            return Err(io::Error::new(ErrorKind::InvalidData, line));
+        };
+
+        let key = (material.parse::<u32>(), score.parse::<i16>());
+        let (Ok(material), Ok(score)) = key else {
+            return Err(io::Error::new(ErrorKind::InvalidData, line));
+        };
+
+        let idx = ["Win", "Draw", "Loss"].iter().position(|&w| w == wdl);
+        let Some(idx) = idx else {
+            return Err(io::Error::new(ErrorKind::InvalidData, line));
+        };
+
+        counts.entry((material, score)).or_default()[idx] += 1.;
+    }
+
+    let samples = Vec::from_iter(
+        counts
+            .into_iter()
+            .map(|((material, score), counts)| (material as f64 / 58., score as f64, counts)),
+    );
+
+    // Restart the search from the best coefficients found until it stops improving.
+    let mut coefficients = [0., 0., 0., 25., 0., 0., 0., 35.];
+    let mut best = f64::INFINITY;
+    while nll(&samples, coefficients) < best {
+        best = nll(&samples, coefficients);
+        coefficients = minimize(|c| nll(&samples, c), coefficients, 10.);
+        eprintln!(
+            "negative log-likelihood: {:.3}",
+            nll(&samples, coefficients)
+        );
+    }
+
+    println!("A = {:.3?}", &coefficients[..4]);
+    println!("B = {:.3?}", &coefficients[4..]);
+    Ok(())
+}
+
+fn main() -> io::Result<()> {
+    let args = Vec::from_iter(env::args().skip(1));
+    match &args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["play", games, nodes, path] => {
+            let games = games.parse().map_err(io::Error::other)?;
+            let nodes = nodes.parse().map_err(io::Error::other)?;
+            play(games, nodes, &mut BufWriter::new(File::create(path)?))
+        }
+
+        ["fit", path] => fit(BufReader::new(File::open(path)?)),
+        _ => Err(io::Error::other(
+            "usage: wdl play <games> <nodes> <data.csv> | wdl fit <data.csv>",
+        )),
+    }
+}
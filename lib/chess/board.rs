@@ -26,6 +26,22 @@ pub struct Board {
     }))]
     colors: [Bitboard; 2],
     pub turn: Color,
+    #[cfg_attr(test, map(|cr: Castles| {
+        let board = Board { roles: #roles, colors: #colors, ..Board::default() };
+        Color::iter().fold(Castles::none(), |castles, side| {
+            let king = board.king(side).map(|sq| sq.file());
+            let rook = |sq: Square| board.castling_rook(side, sq.file());
+            let castles = match cr.short(side).and_then(rook) {
+                Some(sq) if Some(sq.file()) > king => castles.with_short(side, sq.file()),
+                _ => castles,
+            };
+
+            match cr.long(side).and_then(rook) {
+                Some(sq) if Some(sq.file()) < king => castles.with_long(side, sq.file()),
+                _ => castles,
+            }
+        })
+    }))]
     pub castles: Castles,
     pub en_passant: Option<Square>,
     pub halfmoves: u8,
@@ -130,6 +146,73 @@ impl Board {
         zobrist
     }
 
+    /// The [`Square`] of the rook on the given [`File`] that the king of a [`Color`] could castle with.
+    #[inline(always)]
+    pub fn castling_rook(&self, side: Color, file: File) -> Option<Square> {
+        let king = self.king(side)?;
+        let rook = Square::new(file, Rank::First.perspective(side));
+        let is_rook = self.piece_on(rook) == Some(Piece::new(Role::Rook, side));
+        (is_rook && king.rank() == rook.rank()).then_some(rook)
+    }
+
+    /// Draws the board as ASCII art, from white's point of view.
+    pub fn ascii(&self) -> String {
+        let mut ascii = String::new();
+        let separator = " +---+---+---+---+---+---+---+---+\n";
+
+        for rank in Rank::iter().rev() {
+            ascii.push_str(separator);
+            for file in File::iter() {
+                match self.piece_on(Square::new(file, rank)) {
+                    None => ascii.push_str(" |  "),
+                    Some(p) => write!(ascii, " | {p}").assume(),
+                }
+            }
+
+            writeln!(ascii, " | {rank}").assume();
+        }
+
+        ascii.push_str(separator);
+        ascii.push_str("   a   b   c   d   e   f   g   h");
+        ascii
+    }
+
+    /// Parses the castling rights in either [X-FEN] or [Shredder-FEN].
+    ///
+    /// [X-FEN]: https://www.chessprogramming.org/X-FEN
+    /// [Shredder-FEN]: https://www.chessprogramming.org/Forsyth-Edwards_Notation#Shredder-FEN
+    fn parse_castles(&self, s: &str) -> Option<Castles> {
+        let mut castles = Castles::none();
+
+        for c in s.chars() {
+            let mut buffer = [0; 4];
+            let side = if c.is_ascii_uppercase() {
+                Color::White
+            } else {
+                Color::Black
+            };
+            let king = self.king(side)?.file();
+            let rook = match c.to_ascii_lowercase() {
+                'k' => File::iter()
+                    .rev()
+                    .take_while(|&f| f > king)
+                    .find_map(|f| self.castling_rook(side, f)),
+                'q' => File::iter()
+                    .take_while(|&f| f < king)
+                    .find_map(|f| self.castling_rook(side, f)),
+                f => self.castling_rook(side, File::from_str(f.encode_utf8(&mut buffer)).ok()?),
+            }?;
+
+            castles = match rook.file() {
+                f if f > king && !castles.has_short(side) => castles.with_short(side, f),
+                f if f < king && !castles.has_long(side) => castles.with_long(side, f),
+                _ => return None,
+            };
+        }
+
+        Some(castles)
+    }
+
     /// Toggles a piece on a square.
     #[inline(always)]
     pub fn toggle(&mut self, p: Piece, sq: Square) {
@@ -209,6 +292,13 @@ pub enum ParseFenError {
     InvalidSyntax,
 }
 
+/// Parses a board from its [FEN] representation.
+///
+/// The halfmove clock and the fullmove number may be omitted, as in [EPD],
+/// in which case they default to 0 and 1 respectively.
+///
+/// [FEN]: https://www.chessprogramming.org/Forsyth-Edwards_Notation
+/// [EPD]: https://www.chessprogramming.org/Extended_Position_Description
 impl FromStr for Board {
     type Err = ParseFenError;
 
@@ -248,14 +338,7 @@ impl FromStr for Board {
             _ => return Err(ParseFenError::InvalidSideToMove),
         };
 
-        let castles = match tokens.next() {
-            None => return Err(ParseFenError::InvalidCastlingRights),
-            Some("-") => Castles::none(),
-            Some(s) => match s.parse() {
-                Err(_) => return Err(ParseFenError::InvalidCastlingRights),
-                Ok(castles) => castles,
-            },
-        };
+        let castles = tokens.next().ok_or(ParseFenError::InvalidCastlingRights)?;
 
         let en_passant = match tokens.next() {
             None => return Err(ParseFenError::InvalidEnPassantSquare),
@@ -266,27 +349,40 @@ impl FromStr for Board {
             },
         };
 
-        let Some(Ok(halfmoves)) = tokens.next().map(u8::from_str) else {
-            return Err(ParseFenError::InvalidHalfmoveClock);
+        let halfmoves = match tokens.next().map(u8::from_str) {
+            None => 0,
+            Some(Ok(halfmoves)) => halfmoves,
+            Some(Err(_)) => return Err(ParseFenError::InvalidHalfmoveClock),
         };
 
-        let Some(Ok(fullmoves)) = tokens.next().map(u32::from_str) else {
-            return Err(ParseFenError::InvalidHalfmoveClock);
+        let fullmoves = match tokens.next().map(u32::from_str) {
+            None => 1,
+            Some(Ok(fullmoves)) => fullmoves,
+            Some(Err(_)) => return Err(ParseFenError::InvalidFullmoveNumber),
         };
 
         if tokens.next().is_some() {
             return Err(ParseFenError::InvalidSyntax);
         }
 
-        Ok(Board {
+        let mut board = Board {
             roles,
             colors,
             turn,
-            castles,
+            castles: Castles::none(),
             en_passant,
             halfmoves,
             fullmoves,
-        })
+        };
+
+        if castles != "-" {
+            match board.parse_castles(castles) {
+                None => return Err(ParseFenError::InvalidCastlingRights),
+                Some(castles) => board.castles = castles,
+            }
+        }
+
+        Ok(board)
     }
 }
 
@@ -296,6 +392,22 @@ mod tests {
     use std::fmt::Debug;
     use test_strategy::proptest;
 
+    #[proptest]
+    fn ascii_draws_every_piece_on_its_square(b: Board) {
+        let ascii = b.ascii();
+        let lines: Vec<_> = ascii.lines().collect();
+        assert_eq!(lines.len(), 18);
+
+        for sq in Square::iter() {
+            let row = lines[2 * (7 - sq.rank() as usize) + 1];
+            let cell = &row[4 * sq.file() as usize + 3..][..1];
+            match b.piece_on(sq) {
+                None => assert_eq!(cell, " "),
+                Some(p) => assert_eq!(cell, p.to_string()),
+            }
+        }
+    }
+
     #[proptest]
     fn iter_returns_pieces_and_squares(b: Board) {
         for (p, sq) in b.iter() {
@@ -374,6 +486,109 @@ mod tests {
         assert_eq!(b.to_string().parse(), Ok(b));
     }
 
+    #[proptest]
+    fn parsing_board_defaults_missing_move_counters(b: Board) {
+        let fen = b.to_string();
+        let mut fields: Vec<_> = fen.split_ascii_whitespace().collect();
+
+        fields.pop();
+        let board: Board = fields.join(" ").parse()?;
+        assert_eq!(
+            board,
+            Board {
+                fullmoves: 1,
+                ..b.clone()
+            }
+        );
+
+        fields.pop();
+        let board: Board = fields.join(" ").parse()?;
+        assert_eq!(
+            board,
+            Board {
+                halfmoves: 0,
+                fullmoves: 1,
+                ..b
+            }
+        );
+    }
+
+    #[proptest]
+    fn parsing_board_fails_if_halfmove_clock_is_invalid(
+        b: Board,
+        #[strategy("[a-z-]+|[3-9][0-9]{2}")] s: String,
+    ) {
+        let fen = b.to_string();
+        let fields: Vec<_> = fen.split_ascii_whitespace().take(4).collect();
+        let fen = format!("{} {s}", fields.join(" "));
+        assert_eq!(
+            fen.parse::<Board>(),
+            Err(ParseFenError::InvalidHalfmoveClock)
+        );
+    }
+
+    #[proptest]
+    fn parsing_board_fails_if_fullmove_number_is_invalid(
+        b: Board,
+        #[strategy("[a-z-]+")] s: String,
+    ) {
+        let fen = b.to_string();
+        let fields: Vec<_> = fen.split_ascii_whitespace().take(5).collect();
+        let fen = format!("{} {s}", fields.join(" "));
+        assert_eq!(
+            fen.parse::<Board>(),
+            Err(ParseFenError::InvalidFullmoveNumber)
+        );
+    }
+
+    #[test]
+    fn parsing_board_accepts_castles_in_xfen_and_shredder_fen() {
+        let xfen = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9";
+        let shredder = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9";
+        assert_eq!(xfen.parse::<Board>(), shredder.parse::<Board>());
+
+        let board: Board = shredder.parse().unwrap();
+        assert_eq!(board.castles.short(Color::White), Some(Square::H1));
+        assert_eq!(board.castles.long(Color::White), Some(Square::F1));
+        assert_eq!(board.castles.short(Color::Black), Some(Square::H8));
+        assert_eq!(board.castles.long(Color::Black), Some(Square::F8));
+    }
+
+    #[test]
+    fn parsing_board_resolves_xfen_castles_to_the_outermost_rook() {
+        let board: Board = "1r2k1rr/8/8/8/8/8/8/1R2K1RR w Kq - 0 1".parse().unwrap();
+        assert_eq!(board.castles.short(Color::White), Some(Square::H1));
+        assert_eq!(board.castles.long(Color::Black), Some(Square::B8));
+        assert_eq!(board.castles.to_string(), "Hb");
+    }
+
+    #[proptest]
+    fn parsing_board_fails_if_castling_right_is_duplicated(
+        #[filter(!#s.is_empty())]
+        #[strategy("(KK)?(kk)?(QQ)?(qq)?(HK)?(aq)?")]
+        s: String,
+    ) {
+        let fen = format!("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w {s} - 0 1");
+        assert_eq!(
+            fen.parse::<Board>(),
+            Err(ParseFenError::InvalidCastlingRights)
+        );
+    }
+
+    #[proptest]
+    fn parsing_board_fails_if_castling_rook_is_missing(
+        #[strategy("[KQkqBCDEFGbcdefg]")] s: String,
+    ) {
+        let fen = format!("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w {s} - 0 1");
+        let fen = fen
+            .replace("RNBQKBNR", "1NBQKBN1")
+            .replace("rnbqkbnr", "1nbqkbn1");
+        assert_eq!(
+            fen.parse::<Board>(),
+            Err(ParseFenError::InvalidCastlingRights)
+        );
+    }
+
     #[proptest]
     fn parsing_board_fails_for_invalid_fen(
         b: Board,
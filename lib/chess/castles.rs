@@ -1,205 +1,199 @@
-use crate::chess::{Color, Perspective, Piece, Role, Square};
-use crate::util::{Bits, Integer};
-use derive_more::with_trait::{Debug, *};
-use std::fmt::{self, Formatter};
-use std::{cell::SyncUnsafeCell, mem::MaybeUninit, str::FromStr};
+use crate::chess::{Color, File, Perspective, Piece, Rank, Role, Square};
+use crate::util::Integer;
+use derive_more::with_trait::{Debug, Display};
+use std::fmt::{self, Formatter, Write};
 
 /// The castling rights in a chess [`Position`][`crate::chess::Position`].
-#[derive(
-    Debug,
-    Copy,
-    Clone,
-    Eq,
-    PartialEq,
-    Hash,
-    BitAnd,
-    BitAndAssign,
-    BitOr,
-    BitOrAssign,
-    BitXor,
-    BitXorAssign,
-    Not,
-)]
+///
+/// Every right is tied to the [`File`] of the rook it castles with, as required by [Chess960].
+///
+/// [Chess960]: https://www.chessprogramming.org/Chess960
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(test, derive(test_strategy::Arbitrary))]
 #[debug("Castles({self})")]
-pub struct Castles(Bits<u8, 4>);
+pub struct Castles([Option<File>; 4]);
 
 impl Castles {
     /// No castling rights.
     #[inline(always)]
     pub fn none() -> Self {
-        Castles(Bits::new(0b0000))
+        Castles([None; 4])
     }
 
     /// All castling rights.
     #[inline(always)]
     pub fn all() -> Self {
-        Castles(Bits::new(0b1111))
-    }
-
-    /// A unique number the represents this castling rights configuration.
-    #[inline(always)]
-    pub fn index(&self) -> u8 {
-        self.0.get()
+        Castles([Some(File::H), Some(File::A), Some(File::H), Some(File::A)])
     }
 
     /// Whether the given side has kingside castling rights.
     #[inline(always)]
     pub fn has_short(&self, side: Color) -> bool {
-        *self & Castles::from(Square::H1.perspective(side)) != Castles::none()
+        self.0[2 * side as usize].is_some()
     }
 
     /// Whether the given side has queenside castling rights.
     #[inline(always)]
     pub fn has_long(&self, side: Color) -> bool {
-        *self & Castles::from(Square::A1.perspective(side)) != Castles::none()
+        self.0[2 * side as usize + 1].is_some()
     }
 
-    /// The kingside castling square, if side has the rights.
+    /// The square of the rook that castles kingside, if side has the rights.
     #[inline(always)]
     pub fn short(&self, side: Color) -> Option<Square> {
-        if self.has_short(side) {
-            Some(Square::G1.perspective(side))
-        } else {
-            None
-        }
+        let rank = Rank::First.perspective(side);
+        self.0[2 * side as usize].map(|f| Square::new(f, rank))
     }
 
-    /// The queenside castling square, if side has the rights.
+    /// The square of the rook that castles queenside, if side has the rights.
     #[inline(always)]
     pub fn long(&self, side: Color) -> Option<Square> {
-        if self.has_long(side) {
-            Some(Square::C1.perspective(side))
-        } else {
-            None
-        }
+        let rank = Rank::First.perspective(side);
+        self.0[2 * side as usize + 1].map(|f| Square::new(f, rank))
     }
-}
 
-impl Default for Castles {
+    /// Grants kingside castling rights with the rook on the given [`File`].
     #[inline(always)]
-    fn default() -> Self {
-        Castles::all()
+    pub fn with_short(mut self, side: Color, file: File) -> Self {
+        self.0[2 * side as usize] = Some(file);
+        self
     }
-}
 
-impl From<Square> for Castles {
+    /// Grants queenside castling rights with the rook on the given [`File`].
     #[inline(always)]
-    fn from(sq: Square) -> Self {
-        mod global {
-            use super::*;
-
-            pub static CASTLES: SyncUnsafeCell<[Castles; 64]> =
-                unsafe { MaybeUninit::zeroed().assume_init() };
-
-            #[cold]
-            #[ctor::ctor]
-            #[inline(never)]
-            unsafe fn init() {
-                let castles = unsafe { CASTLES.get().as_mut_unchecked() };
-                castles[Square::A1 as usize] = Castles(Bits::new(0b0010));
-                castles[Square::H1 as usize] = Castles(Bits::new(0b0001));
-                castles[Square::E1 as usize] = Castles(Bits::new(0b0011));
-                castles[Square::A8 as usize] = Castles(Bits::new(0b1000));
-                castles[Square::H8 as usize] = Castles(Bits::new(0b0100));
-                castles[Square::E8 as usize] = Castles(Bits::new(0b1100));
-            }
-        }
+    pub fn with_long(mut self, side: Color, file: File) -> Self {
+        self.0[2 * side as usize + 1] = Some(file);
+        self
+    }
 
-        unsafe { global::CASTLES.get().as_ref_unchecked()[sq as usize] }
+    /// Revokes all castling rights of the given side.
+    #[inline(always)]
+    pub fn without(mut self, side: Color) -> Self {
+        self.0[2 * side as usize..][..2].fill(None);
+        self
     }
-}
 
-impl Display for Castles {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    /// Revokes the castling rights tied to the rook on the given [`Square`], if any.
+    #[inline(always)]
+    pub fn without_rook(mut self, sq: Square) -> Self {
         for side in Color::iter() {
-            if self.has_short(side) {
-                Display::fmt(&Piece::new(Role::King, side), f)?;
+            if self.short(side) == Some(sq) {
+                self.0[2 * side as usize] = None;
             }
 
-            if self.has_long(side) {
-                Display::fmt(&Piece::new(Role::Queen, side), f)?;
+            if self.long(side) == Some(sq) {
+                self.0[2 * side as usize + 1] = None;
             }
         }
 
-        Ok(())
+        self
     }
-}
-
-/// The reason why parsing [`Castles`] failed.
-#[derive(Debug, Display, Clone, Eq, PartialEq, Error)]
-#[display("failed to parse castling rights")]
-pub struct ParseCastlesError;
-
-impl FromStr for Castles {
-    type Err = ParseCastlesError;
 
+    /// Whether the rooks are on their files of the standard starting position.
     #[inline(always)]
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut castles = Castles::none();
-
-        use Piece::*;
-        for c in s.chars() {
-            let mut buffer = [0; 4];
+    fn is_standard(&self) -> bool {
+        Color::iter().all(|side| {
+            self.short(side).is_none_or(|sq| sq.file() == File::H)
+                && self.long(side).is_none_or(|sq| sq.file() == File::A)
+        })
+    }
+}
 
-            match Piece::from_str(c.encode_utf8(&mut buffer)) {
-                Ok(p @ WhiteKing | p @ BlackKing) if !castles.has_short(p.color()) => {
-                    castles |= Castles::from(Square::H1.perspective(p.color()));
-                }
+impl Default for Castles {
+    #[inline(always)]
+    fn default() -> Self {
+        Castles::all()
+    }
+}
 
-                Ok(p @ WhiteQueen | p @ BlackQueen) if !castles.has_long(p.color()) => {
-                    castles |= Castles::from(Square::A1.perspective(p.color()));
+/// Prints the castling rights as in FEN if the rooks are on their standard files,
+/// otherwise as in [Shredder-FEN].
+///
+/// [Shredder-FEN]: https://www.chessprogramming.org/Forsyth-Edwards_Notation#Shredder-FEN
+impl Display for Castles {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let standard = self.is_standard();
+        for side in Color::iter() {
+            for (rook, role) in [
+                (self.short(side), Role::King),
+                (self.long(side), Role::Queen),
+            ] {
+                match rook {
+                    None => continue,
+                    Some(_) if standard => Display::fmt(&Piece::new(role, side), f)?,
+                    Some(sq) => {
+                        let file = char::from(b'a' + sq.file().cast::<u8>());
+                        match side {
+                            Color::White => f.write_char(file.to_ascii_uppercase())?,
+                            Color::Black => f.write_char(file)?,
+                        }
+                    }
                 }
-
-                _ => return Err(ParseCastlesError),
             }
         }
 
-        Ok(castles)
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::chess::ZobristNumbers;
     use std::fmt::Debug;
     use test_strategy::proptest;
 
     #[proptest]
-    fn short_returns_kingside_castle_square(cr: Castles, #[filter(#cr.has_short(#c))] c: Color) {
-        assert_eq!(cr.short(c), Some(Square::G1.perspective(c)));
+    fn short_returns_square_of_kingside_rook(cr: Castles, c: Color, f: File) {
+        let sq = Square::new(f, Rank::First.perspective(c));
+        assert_eq!(cr.with_short(c, f).short(c), Some(sq));
     }
 
     #[proptest]
-    fn long_returns_queenside_castle_square(cr: Castles, #[filter(#cr.has_long(#c))] c: Color) {
-        assert_eq!(cr.long(c), Some(Square::C1.perspective(c)));
+    fn long_returns_square_of_queenside_rook(cr: Castles, c: Color, f: File) {
+        let sq = Square::new(f, Rank::First.perspective(c));
+        assert_eq!(cr.with_long(c, f).long(c), Some(sq));
     }
 
     #[proptest]
-    fn parsing_printed_castles_is_an_identity(cr: Castles) {
-        assert_eq!(cr.to_string().parse(), Ok(cr));
+    fn without_revokes_all_rights_of_side(cr: Castles, c: Color) {
+        let castles = cr.without(c);
+        assert!(!castles.has_short(c) && !castles.has_long(c));
+        assert_eq!(castles.short(!c), cr.short(!c));
+        assert_eq!(castles.long(!c), cr.long(!c));
     }
 
     #[proptest]
-    fn parsing_castles_fails_if_right_is_duplicated(
-        #[filter(!#s.is_empty())]
-        #[strategy("(KK)?(kk)?(QQ)?(qq)?")]
-        s: String,
-    ) {
-        assert_eq!(Castles::from_str(&s), Err(ParseCastlesError));
+    fn without_rook_revokes_right_tied_to_rook(cr: Castles, #[filter(#cr.has_short(#c))] c: Color) {
+        let castles = cr.without_rook(cr.short(c).unwrap());
+        assert!(!castles.has_short(c));
+        assert_eq!(
+            castles.long(c),
+            cr.long(c).filter(|&sq| Some(sq) != cr.short(c))
+        );
     }
 
     #[proptest]
-    fn parsing_castles_fails_for_invalid_string(
-        c: Castles,
-        #[strategy(..=#c.to_string().len())] n: usize,
-        #[strategy("[^[:ascii:]]+")] r: String,
-    ) {
-        let s = c.to_string();
+    fn zobrist_is_unique_for_each_set_of_rights_and_rooks(a: Castles, b: Castles) {
+        let zobrist = ZobristNumbers::castling;
+        assert_eq!(zobrist(a) == zobrist(b), a == b);
+    }
 
+    #[test]
+    fn standard_castles_are_printed_as_in_fen() {
+        assert_eq!(Castles::all().to_string(), "KQkq");
         assert_eq!(
-            [&s[..n], &r, &s[n..]].concat().parse().ok(),
-            None::<Castles>
+            Castles::none().with_long(Color::Black, File::A).to_string(),
+            "q"
         );
     }
+
+    #[test]
+    fn chess960_castles_are_printed_as_in_shredder_fen() {
+        let castles = Castles::all()
+            .with_short(Color::White, File::G)
+            .with_long(Color::Black, File::B);
+
+        assert_eq!(castles.to_string(), "GAhb");
+    }
 }
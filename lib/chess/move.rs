@@ -1,4 +1,4 @@
-use crate::chess::{Bitboard, Perspective, Piece, Rank, Role, Square, Squares};
+use crate::chess::{Bitboard, File, Perspective, Piece, Rank, Role, Square, Squares};
 use crate::util::{Assume, Binary, Bits, Integer};
 use std::fmt::{self, Debug, Display, Formatter, Write};
 use std::{num::NonZeroU16, ops::RangeBounds};
@@ -40,6 +40,14 @@ impl Move {
         m
     }
 
+    /// Constructs a castling move, encoded as the king capturing its own rook.
+    #[inline(always)]
+    pub fn castling(king: Square, rook: Square) -> Self {
+        let mut m = Self::regular(king, rook, None);
+        m.0 |= 0b001;
+        m
+    }
+
     /// The source [`Square`].
     #[inline(always)]
     pub fn whence(&self) -> Square {
@@ -47,6 +55,8 @@ impl Move {
     }
 
     /// The destination [`Square`].
+    ///
+    /// For castling moves, this is the square of the rook.
     #[inline(always)]
     pub fn whither(&self) -> Square {
         Square::decode(self.bits(4..).pop())
@@ -74,11 +84,33 @@ impl Move {
         self.bits(3..=3) != Bits::new(0)
     }
 
+    /// Whether this is a castling move.
+    #[inline(always)]
+    pub fn is_castling(&self) -> bool {
+        self.bits(..4) == Bits::new(0b0001)
+    }
+
     /// Whether this move is neither a capture nor a promotion.
     #[inline(always)]
     pub fn is_quiet(&self) -> bool {
         self.bits(2..=3) == Bits::new(0)
     }
+
+    /// The [`Square`]s the king and the rook end up on, if this is a castling move.
+    #[inline(always)]
+    pub fn castling_squares(&self) -> Option<(Square, Square)> {
+        if !self.is_castling() {
+            return None;
+        }
+
+        let (king, rook) = (self.whence(), self.whither());
+        let (k, r) = if rook > king {
+            (File::G, File::F)
+        } else {
+            (File::C, File::D)
+        };
+        Some((Square::new(k, king.rank()), Square::new(r, king.rank())))
+    }
 }
 
 impl Debug for Move {
@@ -94,10 +126,18 @@ impl Debug for Move {
     }
 }
 
+/// Castling moves are printed as the king moving two squares,
+/// or as the king capturing its own rook with the alternate flag `{:#}`, as in [Chess960].
+///
+/// [Chess960]: https://www.chessprogramming.org/Chess960
 impl Display for Move {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         Display::fmt(&self.whence(), f)?;
-        Display::fmt(&self.whither(), f)?;
+
+        match self.castling_squares() {
+            Some((king, _)) if !f.alternate() => Display::fmt(&king, f)?,
+            _ => Display::fmt(&self.whither(), f)?,
+        }
 
         if let Some(r) = self.promotion() {
             Display::fmt(&r, f)?;
@@ -152,6 +192,16 @@ impl MoveSet {
         moves
     }
 
+    /// A set of castling moves given the squares of the rooks.
+    #[inline(always)]
+    pub fn castling(king: Square, rooks: Bitboard) -> Self {
+        let base = Move::castling(king, king.flip());
+        MoveSet {
+            base,
+            whither: rooks,
+        }
+    }
+
     /// The source [`Square`].
     #[inline(always)]
     pub fn whence(&self) -> Square {
@@ -176,6 +226,12 @@ impl MoveSet {
         self.base.is_promotion()
     }
 
+    /// Whether the moves in this set are castling moves.
+    #[inline(always)]
+    pub fn is_castling(&self) -> bool {
+        self.base.is_castling()
+    }
+
     /// Whether the moves in this set are neither captures nor promotions.
     #[inline(always)]
     pub fn is_quiet(&self) -> bool {
@@ -311,6 +367,29 @@ mod tests {
         assert!(!Move::capture(wc, wt, Some(p)).is_quiet());
     }
 
+    #[proptest]
+    fn castling_move_can_be_constructed(wc: Square, #[filter(#wc != #wt)] wt: Square) {
+        let m = Move::castling(wc, wt);
+        assert!(m.is_castling() && m.is_quiet());
+        assert_eq!((m.whence(), m.whither(), m.promotion()), (wc, wt, None));
+    }
+
+    #[proptest]
+    fn only_castling_moves_have_castling_squares(m: Move) {
+        assert_eq!(m.castling_squares().is_some(), m.is_castling());
+    }
+
+    #[test]
+    fn castling_move_is_printed_as_king_move_or_as_king_takes_rook() {
+        let m = Move::castling(Square::E1, Square::H1);
+        assert_eq!(m.to_string(), "e1g1");
+        assert_eq!(format!("{m:#}"), "e1h1");
+
+        let m = Move::castling(Square::B8, Square::A8);
+        assert_eq!(m.to_string(), "b8c8");
+        assert_eq!(format!("{m:#}"), "b8a8");
+    }
+
     #[proptest]
     fn can_iterate_moves_in_set(ml: MoveSet) {
         let v = Vec::from_iter(ml);
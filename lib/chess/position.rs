@@ -206,32 +206,46 @@ impl Moves {
                 }
             }
 
-            if let Some(c) = pos.castles().long(turn) {
-                let b = Square::new(File::B, c.rank());
-                let path = c.bitboard().with(Square::new(File::D, c.rank()));
-                if occupied & path.with(b) == Bitboard::empty() {
-                    if !path.iter().any(|sq| pos.is_threatened(sq, !turn, occupied)) {
-                        moves |= c.bitboard();
-                    }
-                }
-            }
+            collect_moves(piece, king, moves, theirs, buffer)?;
 
-            if let Some(g) = pos.castles().short(turn) {
-                let path = g.bitboard().with(Square::new(File::F, g.rank()));
-                if occupied & path == Bitboard::empty() {
-                    if !path.iter().any(|sq| pos.is_threatened(sq, !turn, occupied)) {
-                        moves |= g.bitboard();
+            let mut rooks = Bitboard::empty();
+            for rook in [pos.castles().short(turn), pos.castles().long(turn)] {
+                let Some(rook) = rook else { continue };
+                let (wk, wr) = Move::castling(king, rook).castling_squares().assume();
+                let blockers = occupied.without(king).without(rook);
+                let path = Bitboard::segment(king, wk).with(wk);
+                let span = path | Bitboard::segment(rook, wr).with(wr);
+                if blockers & span == Bitboard::empty() {
+                    if !path.iter().any(|sq| pos.is_threatened(sq, !turn, blockers)) {
+                        rooks |= rook.bitboard();
                     }
                 }
             }
 
-            collect_moves(piece, king, moves, theirs, buffer)?;
+            if !rooks.is_empty() {
+                buffer.try_push(MoveSet::castling(king, rooks))?;
+            }
         }
 
         Ok(())
     }
 }
 
+/// The state needed to take back a [`Move`] played with [`Position::make`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Undo {
+    m: Move,
+    role: Role,
+    capture: Option<(Role, Square)>,
+    zobrist: Zobrist,
+    checkers: Bitboard,
+    pinned: Bitboard,
+    castles: Castles,
+    en_passant: Option<Square>,
+    halfmoves: u8,
+    evicted: Option<NonZeroU32>,
+}
+
 /// The current position on the board board.
 ///
 /// This type guarantees that it only holds valid positions.
@@ -300,6 +314,12 @@ impl Arbitrary for Position {
 }
 
 impl Position {
+    /// The underlying [`Board`].
+    #[inline(always)]
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
     /// The side to move.
     #[inline(always)]
     pub fn turn(&self) -> Color {
@@ -397,7 +417,8 @@ impl Position {
             None => 0,
             hash => {
                 let history = &self.history[self.turn() as usize];
-                history.iter().filter(|h| **h == hash).count()
+                let reversible = history.len().min(self.halfmoves() as usize / 2);
+                history[..reversible].iter().filter(|h| **h == hash).count()
             }
         }
     }
@@ -508,7 +529,7 @@ impl Position {
     /// An iterator over the legal moves that can be played in this position.
     #[inline(always)]
     pub fn moves(&self) -> impl Iterator<Item = MoveSet> {
-        let mut moves = ArrayVec::<_, 32>::new();
+        let mut moves = ArrayVec::<_, 33>::new();
 
         if self.is_check() {
             Evasions::generate(self, &mut moves).assume()
@@ -524,7 +545,7 @@ impl Position {
     pub fn play(&mut self, m: Move) -> (Role, Option<(Role, Square)>) {
         debug_assert!(self.moves().flatten().any(|n| m == n));
 
-        use {Role::*, Square::*};
+        use Role::*;
 
         let turn = self.turn();
         let promotion = m.promotion();
@@ -542,14 +563,14 @@ impl Position {
 
         if role == Pawn || capture.is_some() {
             self.board.halfmoves = 0;
-            self.history = Default::default();
         } else {
             self.board.halfmoves += 1;
-            let entries = self.history[turn as usize].len();
-            self.history[turn as usize].copy_within(..entries - 1, 1);
-            self.history[turn as usize][0] = NonZeroU32::new(self.zobrist().cast());
         }
 
+        let entries = self.history[turn as usize].len();
+        self.history[turn as usize].copy_within(..entries - 1, 1);
+        self.history[turn as usize][0] = NonZeroU32::new(self.zobrist().cast());
+
         self.board.turn = !self.board.turn;
         self.zobrist ^= ZobristNumbers::turn();
 
@@ -562,11 +583,21 @@ impl Position {
             self.zobrist ^= ZobristNumbers::psq(!turn, victim, target);
         }
 
-        self.board.toggle(Piece::new(role, turn), wc);
-        self.board.toggle(Piece::new(role, turn), wt);
-
-        self.zobrist ^= ZobristNumbers::psq(turn, role, wc);
-        self.zobrist ^= ZobristNumbers::psq(turn, role, wt);
+        if let Some((wk, wr)) = m.castling_squares() {
+            self.board.toggle(Piece::new(King, turn), wc);
+            self.board.toggle(Piece::new(Rook, turn), wt);
+            self.board.toggle(Piece::new(King, turn), wk);
+            self.board.toggle(Piece::new(Rook, turn), wr);
+            self.zobrist ^= ZobristNumbers::psq(turn, King, wc);
+            self.zobrist ^= ZobristNumbers::psq(turn, Rook, wt);
+            self.zobrist ^= ZobristNumbers::psq(turn, King, wk);
+            self.zobrist ^= ZobristNumbers::psq(turn, Rook, wr);
+        } else {
+            self.board.toggle(Piece::new(role, turn), wc);
+            self.board.toggle(Piece::new(role, turn), wt);
+            self.zobrist ^= ZobristNumbers::psq(turn, role, wc);
+            self.zobrist ^= ZobristNumbers::psq(turn, role, wt);
+        }
 
         if let Some(promotion) = promotion {
             self.board.toggle(Piece::new(Pawn, turn), wt);
@@ -576,23 +607,16 @@ impl Position {
         } else if role == Pawn && (wt - wc).abs() == 16 {
             self.board.en_passant = Some(Square::new(wc.file(), Rank::Third.perspective(turn)));
             self.zobrist ^= ZobristNumbers::en_passant(wc.file());
-        } else if role == King && (wt - wc).abs() == 2 {
-            let (wc, wt) = if wt > wc {
-                (H1.perspective(turn), F1.perspective(turn))
-            } else {
-                (A1.perspective(turn), D1.perspective(turn))
-            };
-
-            self.board.toggle(Piece::new(Rook, turn), wc);
-            self.board.toggle(Piece::new(Rook, turn), wt);
-            self.zobrist ^= ZobristNumbers::psq(turn, Rook, wc);
-            self.zobrist ^= ZobristNumbers::psq(turn, Rook, wt);
         }
 
-        let disrupted = Castles::from(wc) | Castles::from(wt);
-        if self.castles() & disrupted != Castles::none() {
+        let castles = match self.castles().without_rook(wc).without_rook(wt) {
+            castles if role == King => castles.without(turn),
+            castles => castles,
+        };
+
+        if castles != self.castles() {
             self.zobrist ^= ZobristNumbers::castling(self.castles());
-            self.board.castles &= !disrupted;
+            self.board.castles = castles;
             self.zobrist ^= ZobristNumbers::castling(self.castles());
         }
 
@@ -622,6 +646,75 @@ impl Position {
         (role, capture)
     }
 
+    /// Play a [`Move`] that can later be taken back with [`Position::undo`].
+    #[inline(always)]
+    pub fn make(&mut self, m: Move) -> Undo {
+        let history = &self.history[self.turn() as usize];
+        let evicted = history[history.len() - 1];
+        let (zobrist, checkers, pinned) = (self.zobrist, self.checkers, self.pinned);
+        let (castles, en_passant, halfmoves) =
+            (self.castles(), self.en_passant(), self.halfmoves());
+        let (role, capture) = self.play(m);
+
+        Undo {
+            m,
+            role,
+            capture,
+            zobrist,
+            checkers,
+            pinned,
+            castles,
+            en_passant,
+            halfmoves,
+            evicted,
+        }
+    }
+
+    /// Takes back the last [`Move`] played with [`Position::make`].
+    #[inline(always)]
+    pub fn undo(&mut self, undo: Undo) {
+        use Role::*;
+
+        let turn = !self.turn();
+        let (wc, wt) = (undo.m.whence(), undo.m.whither());
+
+        if let Some(promotion) = undo.m.promotion() {
+            self.board.toggle(Piece::new(promotion, turn), wt);
+            self.board.toggle(Piece::new(Pawn, turn), wt);
+        }
+
+        if let Some((wk, wr)) = undo.m.castling_squares() {
+            self.board.toggle(Piece::new(King, turn), wk);
+            self.board.toggle(Piece::new(Rook, turn), wr);
+            self.board.toggle(Piece::new(King, turn), wc);
+            self.board.toggle(Piece::new(Rook, turn), wt);
+        } else {
+            self.board.toggle(Piece::new(undo.role, turn), wt);
+            self.board.toggle(Piece::new(undo.role, turn), wc);
+        }
+
+        if let Some((victim, target)) = undo.capture {
+            self.board.toggle(Piece::new(victim, !turn), target);
+        }
+
+        if turn == Color::Black {
+            self.board.fullmoves -= 1;
+        }
+
+        let history = &mut self.history[turn as usize];
+        let entries = history.len();
+        history.copy_within(1.., 0);
+        history[entries - 1] = undo.evicted;
+
+        self.board.turn = turn;
+        self.board.castles = undo.castles;
+        self.board.en_passant = undo.en_passant;
+        self.board.halfmoves = undo.halfmoves;
+        self.zobrist = undo.zobrist;
+        self.checkers = undo.checkers;
+        self.pinned = undo.pinned;
+    }
+
     /// Play a [null-move].
     ///
     /// [null-move]: https://www.chessprogramming.org/Null_Move
@@ -713,7 +806,7 @@ impl Display for Position {
 /// The reason why parsing the FEN string failed.
 #[derive(Debug, Display, Clone, Eq, PartialEq, Error, From)]
 pub enum ParsePositionError {
-    #[display("failed to parse position")]
+    #[display("{_0}")]
     InvalidFen(ParseFenError),
     #[display("illegal position")]
     IllegalPosition,
@@ -885,9 +978,10 @@ mod tests {
         assert_ne!(pos, prev);
         assert_ne!(pos.turn(), prev.turn());
 
+        let (wt, _) = m.castling_squares().unwrap_or((m.whither(), m.whither()));
         assert_eq!(pos[m.whence()], None);
         assert_eq!(
-            pos[m.whither()],
+            pos[wt],
             m.promotion()
                 .map(|r| Piece::new(r, prev.turn()))
                 .or_else(|| prev[m.whence()])
@@ -929,6 +1023,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn castling_moves_the_king_and_the_rook() {
+        let mut pos: Position = "rk5r/8/8/8/8/8/8/RK5R w HAha - 0 1".parse().unwrap();
+        pos.play(Move::castling(Square::B1, Square::A1));
+        assert_eq!(pos.to_string(), "rk5r/8/8/8/8/8/8/2KR3R b kq - 1 1");
+        assert_eq!(pos.zobrist(), pos.board.zobrist());
+
+        pos.play(Move::castling(Square::B8, Square::A8));
+        assert_eq!(pos.to_string(), "2kr3r/8/8/8/8/8/8/2KR3R w - - 2 2");
+        assert_eq!(pos.zobrist(), pos.board.zobrist());
+    }
+
+    #[test]
+    fn zobrist_depends_on_the_rook_tied_to_castling_rights() {
+        let a: Position = "3k4/8/8/8/8/8/8/RR1K4 w A - 0 1".parse().unwrap();
+        let b: Position = "3k4/8/8/8/8/8/8/RR1K4 w B - 0 1".parse().unwrap();
+        assert_ne!(a.castles(), b.castles());
+        assert_ne!(a.zobrist(), b.zobrist());
+    }
+
+    #[test]
+    fn castling_is_illegal_if_the_rook_shields_the_king_path() {
+        let pos: Position = "4k3/8/8/8/8/8/8/qR3K2 w B - 0 1".parse().unwrap();
+        assert!(!pos.moves().any(|ms| ms.is_castling()));
+    }
+
     #[proptest]
     #[should_panic]
     fn play_panics_if_move_illegal(
@@ -938,6 +1058,60 @@ mod tests {
         pos.play(m);
     }
 
+    #[proptest]
+    fn undo_reverts_make(
+        #[filter(#pos.outcome().is_none())] mut pos: Position,
+        #[map(|s: Selector| s.select(#pos.moves().flatten()))] m: Move,
+    ) {
+        let prev = pos.clone();
+        let undo = pos.make(m);
+        assert_ne!(pos, prev);
+
+        pos.undo(undo);
+        assert_eq!(pos, prev);
+        assert_eq!(pos.zobrist(), prev.zobrist());
+        assert_eq!(pos.checkers(), prev.checkers());
+        assert_eq!(pos.pinned(), prev.pinned());
+        assert_eq!(pos.history, prev.history);
+    }
+
+    #[proptest]
+    fn make_is_equivalent_to_play(
+        #[filter(#pos.outcome().is_none())] mut pos: Position,
+        #[map(|s: Selector| s.select(#pos.moves().flatten()))] m: Move,
+    ) {
+        let mut next = pos.clone();
+        pos.make(m);
+        next.play(m);
+        assert_eq!(pos, next);
+        assert_eq!(pos.zobrist(), next.zobrist());
+        assert_eq!(pos.checkers(), next.checkers());
+        assert_eq!(pos.pinned(), next.pinned());
+        assert_eq!(pos.history, next.history);
+    }
+
+    #[test]
+    fn repetitions_are_bounded_by_the_last_irreversible_move() {
+        let mut pos = Position::default();
+        for m in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+            let m = pos.moves().flatten().find(|n| n.to_string() == m).unwrap();
+            pos.play(m);
+        }
+
+        assert_eq!(pos.repetitions(), 1);
+
+        let m = pos
+            .moves()
+            .flatten()
+            .find(|n| n.to_string() == "e2e4")
+            .unwrap();
+        let undo = pos.make(m);
+        assert_eq!(pos.repetitions(), 0);
+
+        pos.undo(undo);
+        assert_eq!(pos.repetitions(), 1);
+    }
+
     #[proptest]
     fn pass_updates_position(#[filter(!#pos.is_check())] mut pos: Position) {
         let prev = pos.clone();
@@ -966,6 +1140,7 @@ mod tests {
         let zobrist = NonZeroU32::new(pos.zobrist().cast());
         prop_assume!(zobrist.is_some());
 
+        pos.board.halfmoves = pos.board.halfmoves.max(4);
         pos.history[pos.turn() as usize][..2].clone_from_slice(&[zobrist, zobrist]);
         assert!(pos.is_draw_by_repetition());
         assert_eq!(pos.outcome(), Some(Outcome::DrawByThreefoldRepetition));
@@ -1031,6 +1206,30 @@ mod tests {
         assert_eq!(Position::perft(&pos, Depth::new(5)), 89941194);
     }
 
+    #[cfg(not(coverage))]
+    #[proptest(cases = 1)]
+    fn perft_chess960_1() {
+        // https://www.chessprogramming.org/Chess960_Perft_Results
+        let pos = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9".parse()?;
+        assert_eq!(Position::perft(&pos, Depth::new(5)), 8146062);
+    }
+
+    #[cfg(not(coverage))]
+    #[proptest(cases = 1)]
+    fn perft_chess960_2() {
+        // https://www.chessprogramming.org/Chess960_Perft_Results
+        let pos = "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9".parse()?;
+        assert_eq!(Position::perft(&pos, Depth::new(5)), 16253601);
+    }
+
+    #[cfg(not(coverage))]
+    #[proptest(cases = 1)]
+    fn perft_chess960_3() {
+        // https://www.chessprogramming.org/Chess960_Perft_Results
+        let pos = "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9".parse()?;
+        assert_eq!(Position::perft(&pos, Depth::new(5)), 6417013);
+    }
+
     #[cfg(not(coverage))]
     #[proptest(cases = 1)]
     fn perft_6() {
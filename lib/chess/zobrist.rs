@@ -12,7 +12,7 @@ pub type Zobrist = Bits<u64, 64>;
 #[derive(Debug)]
 pub struct ZobristNumbers {
     pieces: [[[u64; 64]; 6]; 2],
-    castles: [u64; 16],
+    castles: [[[u64; 8]; 2]; 2],
     en_passant: [u64; 8],
     turn: u64,
 }
@@ -38,10 +38,21 @@ impl ZobristNumbers {
         Zobrist::new(psq[color as usize][role as usize][sq as usize])
     }
 
+    /// Every castling right is hashed along with the [`File`] of the rook it castles with.
     #[inline(always)]
     pub fn castling(castles: Castles) -> Zobrist {
         let castling = unsafe { &ZOBRIST.get().as_ref_unchecked().castles };
-        Zobrist::new(castling[castles.index() as usize])
+
+        let mut zobrist = 0;
+        for side in Color::iter() {
+            for (wing, rook) in [castles.short(side), castles.long(side)].iter().enumerate() {
+                if let Some(sq) = rook {
+                    zobrist ^= castling[side as usize][wing][sq.file() as usize];
+                }
+            }
+        }
+
+        Zobrist::new(zobrist)
     }
 
     #[inline(always)]
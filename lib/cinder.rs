@@ -5,7 +5,7 @@
     feature(stdarch_aarch64_prefetch, stdarch_neon_dotprod)
 )]
 #![feature(
-    array_chunks,
+    //COMPAT array_chunks,
     coverage_attribute,
     new_zeroed_alloc,
     ptr_as_ref_unchecked,
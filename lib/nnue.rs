@@ -1,8 +1,10 @@
+use crate::util::Assume;
 use byteorder::{LittleEndian, ReadBytesExt};
 use ruzstd::decoding::StreamingDecoder;
-use std::cell::SyncUnsafeCell;
-use std::io::{self, Read};
-use std::mem::{MaybeUninit, transmute};
+use std::io::{self, ErrorKind, Read};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicPtr, AtomicU64, Ordering};
+use std::{fs, mem::transmute, path::Path, ptr};
 
 mod accumulator;
 mod evaluator;
@@ -28,18 +30,75 @@ struct Nnue {
     hidden: [Hidden<{ Accumulator::POSITIONAL }>; Accumulator::MATERIAL],
 }
 
-static NNUE: SyncUnsafeCell<Nnue> = unsafe { MaybeUninit::zeroed().assume_init() };
+static EMBEDDED: AtomicPtr<Nnue> = AtomicPtr::new(ptr::null_mut());
+static NNUE: AtomicPtr<Nnue> = AtomicPtr::new(ptr::null_mut());
+static RETIRED: AtomicPtr<Nnue> = AtomicPtr::new(ptr::null_mut());
+static GENERATION: AtomicU64 = AtomicU64::new(1);
+static KERNEL: OnceLock<Kernel> = OnceLock::new();
+static UPDATE: OnceLock<Update> = OnceLock::new();
 
 #[cold]
 #[ctor::ctor]
 #[inline(never)]
 unsafe fn init() {
-    let encoded = include_bytes!("nnue/nn.zst").as_slice();
-    let decoder = StreamingDecoder::new(encoded).expect("failed to initialize zstd decoder");
-    unsafe { Nnue::load(NNUE.get().as_mut_unchecked(), decoder).expect("failed to load the NNUE") }
+    let nnue = Nnue::decode(include_bytes!("nnue/nn.zst")).expect("failed to load the NNUE");
+    EMBEDDED.store(Box::into_raw(nnue), Ordering::Release);
+    NNUE.store(EMBEDDED.load(Ordering::Acquire), Ordering::Release);
+    KERNEL.get_or_init(Kernel::detect);
+    UPDATE.get_or_init(Update::kernel);
+}
+
+/// Replaces the [NNUE] by the network stored in a file, either raw or compressed with zstd.
+///
+/// The network is decoded in full and must have exactly the expected size,
+/// otherwise the current network is left untouched.
+/// The network swapped out is kept alive until the next swap,
+/// since [`Evaluator`]s may still be evaluating positions with it.
+///
+/// [NNUE]: https://www.chessprogramming.org/NNUE
+pub fn load<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    let nnue = Nnue::decode(&fs::read(path)?)?;
+    swap(Box::into_raw(nnue));
+    Ok(())
+}
+
+/// Restores the [NNUE] embedded in the binary.
+///
+/// [NNUE]: https://www.chessprogramming.org/NNUE
+pub fn restore() {
+    swap(EMBEDDED.load(Ordering::Acquire));
+}
+
+/// Swaps in a network, retiring the previous one and deallocating the one retired before it.
+fn swap(nnue: *mut Nnue) {
+    let previous = NNUE.swap(nnue, Ordering::AcqRel);
+    if previous != nnue {
+        GENERATION.fetch_add(1, Ordering::AcqRel);
+        let retired = RETIRED.swap(previous, Ordering::AcqRel);
+        if !retired.is_null() && retired != EMBEDDED.load(Ordering::Acquire) {
+            drop(unsafe { Box::from_raw(retired) });
+        }
+    }
 }
 
 impl Nnue {
+    /// The magic number that identifies a zstd frame.
+    const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+    #[cold]
+    #[inline(never)]
+    fn decode(bytes: &[u8]) -> io::Result<Box<Self>> {
+        let mut nnue = unsafe { Box::<Self>::new_zeroed().assume_init() };
+
+        if bytes.starts_with(&Self::ZSTD_MAGIC) {
+            nnue.load(StreamingDecoder::new(bytes).map_err(io::Error::other)?)?;
+        } else {
+            nnue.load(bytes)?;
+        }
+
+        Ok(nnue)
+    }
+
     #[inline(always)]
     fn load<T: Read>(&mut self, mut reader: T) -> io::Result<()> {
         reader.read_i16_into::<LittleEndian>(&mut *self.ft.bias)?;
@@ -67,24 +126,54 @@ impl Nnue {
             })?;
         }
 
-        debug_assert!(reader.read_u8().is_err());
+        match reader.read_u8() {
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => Ok(()),
+            Err(e) => Err(e),
+            Ok(_) => Err(io::Error::new(ErrorKind::InvalidData, "trailing data")),
+        }
+    }
 
-        Ok(())
+    /// Identifies the current network, changing every time a different one is swapped in.
+    #[inline(always)]
+    fn generation() -> u64 {
+        GENERATION.load(Ordering::Acquire)
+    }
+
+    #[inline(always)]
+    fn get() -> &'static Self {
+        unsafe { NNUE.load(Ordering::Acquire).as_ref_unchecked() }
     }
 
     #[inline(always)]
     fn psqt() -> &'static Linear<i32, { Accumulator::MATERIAL }> {
-        unsafe { &NNUE.get().as_ref_unchecked().psqt }
+        &Self::get().psqt
     }
 
     #[inline(always)]
     fn ft() -> &'static Affine<i16, { Accumulator::POSITIONAL }> {
-        unsafe { &NNUE.get().as_ref_unchecked().ft }
+        &Self::get().ft
     }
 
     #[inline(always)]
     fn hidden(phase: usize) -> &'static Hidden<{ Accumulator::POSITIONAL }> {
-        unsafe { NNUE.get().as_ref_unchecked().hidden.get_unchecked(phase) }
+        unsafe { Self::get().hidden.get_unchecked(phase) }
+    }
+
+    /// The kernel selected at startup to update the accumulator.
+    #[inline(always)]
+    fn update() -> Update {
+        *UPDATE.get().assume()
+    }
+
+    /// Transforms the accumulator with the kernel selected at startup.
+    #[inline(always)]
+    fn forward(
+        phase: usize,
+        us: &[i16; Accumulator::POSITIONAL],
+        them: &[i16; Accumulator::POSITIONAL],
+    ) -> i32 {
+        let kernel = *KERNEL.get().assume();
+        unsafe { Self::hidden(phase).forward(kernel, us, them) }
     }
 }
 
@@ -92,6 +181,76 @@ impl Nnue {
 mod tests {
     use super::*;
     use arrayvec::ArrayVec;
+    use std::env::temp_dir;
+
+    #[test]
+    fn decode_accepts_compressed_and_raw_networks() -> io::Result<()> {
+        let encoded = include_bytes!("nnue/nn.zst");
+        let mut decoded = Vec::new();
+        StreamingDecoder::new(encoded.as_slice())
+            .map_err(io::Error::other)?
+            .read_to_end(&mut decoded)?;
+
+        for bytes in [encoded.as_slice(), &decoded] {
+            assert!(*Nnue::decode(bytes)? == *Nnue::get());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn load_swaps_in_compressed_and_raw_networks() -> io::Result<()> {
+        let encoded = include_bytes!("nnue/nn.zst");
+        let mut decoded = Vec::new();
+        StreamingDecoder::new(encoded.as_slice())
+            .map_err(io::Error::other)?
+            .read_to_end(&mut decoded)?;
+
+        let embedded = unsafe { EMBEDDED.load(Ordering::Acquire).as_ref_unchecked() };
+        for (name, bytes) in [("nn.zst", encoded.as_slice()), ("nn.bin", &decoded)] {
+            let path = temp_dir().join(format!("cinder-{}-{name}", std::process::id()));
+            fs::write(&path, bytes)?;
+            let result = load(&path);
+            fs::remove_file(&path)?;
+            result?;
+
+            assert!(!ptr::eq(Nnue::get(), embedded));
+            assert!(ptr::eq(RETIRED.load(Ordering::Acquire), embedded));
+            assert!(*Nnue::get() == *embedded);
+            restore();
+            assert!(ptr::eq(Nnue::get(), embedded));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn decode_rejects_truncated_or_oversized_networks() -> io::Result<()> {
+        let encoded = include_bytes!("nnue/nn.zst");
+        let mut decoded = Vec::new();
+        StreamingDecoder::new(encoded.as_slice())
+            .map_err(io::Error::other)?
+            .read_to_end(&mut decoded)?;
+
+        let truncated = decoded[..decoded.len() - 1].to_vec();
+        let oversized = [&decoded[..], &[0]].concat();
+
+        for (bytes, kind) in [
+            (truncated, ErrorKind::UnexpectedEof),
+            (oversized, ErrorKind::InvalidData),
+        ] {
+            let result = Nnue::decode(&bytes).map(drop);
+            assert_eq!(result.map_err(|e| e.kind()), Err(kind));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn load_fails_if_file_is_missing() {
+        let path = temp_dir().join(format!("cinder-{}-missing.nn", std::process::id()));
+        assert!(load(path).is_err());
+    }
 
     #[test]
     fn feature_transformer_does_not_overflow() {
@@ -101,7 +260,7 @@ mod tests {
                 Nnue::ft().weight.iter().map(|a| a[i] as i32),
             );
 
-            for weights in features.array_chunks_mut::<768>() {
+            for weights in features.as_chunks_mut::<768>().0.iter_mut() {
                 let (small, _, _) = weights.select_nth_unstable(32);
                 assert!(small.iter().fold(bias, |s, &v| s + v).abs() <= i16::MAX as i32);
                 let (_, _, large) = weights.select_nth_unstable(735);
@@ -35,6 +35,12 @@ impl Accumulator {
         self.positional[side as usize] = Nnue::ft().fresh();
     }
 
+    #[inline(always)]
+    pub fn copy(&mut self, side: Color, other: &Self) {
+        self.material[side as usize] = other.material[side as usize];
+        self.positional[side as usize] = other.positional[side as usize];
+    }
+
     #[inline(always)]
     pub fn update(&mut self, side: Color, sub: [Option<Feature>; 2], add: [Option<Feature>; 2]) {
         match (sub, add) {
@@ -43,6 +49,11 @@ impl Accumulator {
                 Nnue::ft().add(a1, &mut self.positional[side as usize]);
             }
 
+            ([Some(s1), None], [None, None]) => {
+                Nnue::psqt().sub(s1, &mut self.material[side as usize]);
+                Nnue::ft().sub(s1, &mut self.positional[side as usize]);
+            }
+
             ([Some(s1), None], [Some(a1), None]) => {
                 Nnue::psqt().sub_add(s1, a1, &mut self.material[side as usize]);
                 Nnue::ft().sub_add(s1, a1, &mut self.positional[side as usize]);
@@ -69,7 +80,7 @@ impl Accumulator {
         let us = turn as usize;
         let them = turn.flip() as usize;
         let material = self.material[us][phase] - self.material[them][phase];
-        let positional = Nnue::hidden(phase).forward(&self.positional[us], &self.positional[them]);
+        let positional = Nnue::forward(phase, &self.positional[us], &self.positional[them]);
         material + 2 * positional
     }
 }
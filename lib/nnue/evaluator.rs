@@ -1,20 +1,203 @@
-use crate::chess::{Color, Move, ParsePositionError, Perspective, Piece, Position, Role, Square};
+use crate::chess::{Bitboard, Color, Move, ParsePositionError, Piece, Position, Role, Square};
 use crate::nnue::{Accumulator, Feature, Nnue, Value};
 use crate::util::{Assume, Integer};
 use derive_more::with_trait::{Debug, Deref, Display};
-use std::str::FromStr;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::{cell::UnsafeCell, str::FromStr};
 
 #[cfg(test)]
 use proptest::prelude::*;
 
+/// A pending update to one side of an [`Accumulator`].
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+enum Delta {
+    #[default]
+    Refresh,
+    Update([Option<Feature>; 2], [Option<Feature>; 2]),
+}
+
+/// An [`Accumulator`] that is only computed once it's needed.
+#[derive(Debug, Default, Clone)]
+struct Frame {
+    id: u64,
+    parent: u64,
+    deltas: [Delta; 2],
+    computed: [bool; 2],
+    acc: Accumulator,
+}
+
+/// The last [`Accumulator`] computed for some king bucket.
+#[derive(Debug, Default, Clone)]
+struct Entry {
+    acc: Accumulator,
+    pieces: [[Bitboard; 12]; 2],
+}
+
+/// A per-thread stack of lazily updated [`Accumulator`]s.
+///
+/// Frames are identified by the [`Evaluator`] they belong to,
+/// so frames that have since been overwritten are simply refreshed,
+/// as are all frames once a different network is swapped in.
+///
+/// Refreshes are themselves incremental, as only the differences to the last position
+/// refreshed within the same king bucket are applied, see [Finny tables].
+///
+/// [Finny tables]: https://www.chessprogramming.org/NNUE#Accumulator_Refresh_Table
+#[derive(Debug)]
+struct Stack {
+    next: u64,
+    frames: Vec<Frame>,
+    generation: u64,
+    cache: Vec<Entry>,
+}
+
+impl Stack {
+    const LEN: usize = 128;
+    const BLOCK: u64 = 1 << 32;
+
+    #[inline(always)]
+    fn with<R>(f: impl FnOnce(&mut Self) -> R) -> R {
+        thread_local! {
+            static STACK: UnsafeCell<Stack> = UnsafeCell::new(Stack {
+                next: 0,
+                frames: vec![Frame::default(); Stack::LEN],
+                generation: 0,
+                cache: Vec::new(),
+            });
+        }
+
+        STACK.with(|s| f(unsafe { &mut *s.get() }))
+    }
+
+    /// A unique identifier across all threads.
+    #[inline(always)]
+    fn id(&mut self) -> u64 {
+        static IDS: AtomicU64 = AtomicU64::new(0);
+
+        if self.next.is_multiple_of(Self::BLOCK) {
+            self.next = IDS.fetch_add(Self::BLOCK, Ordering::Relaxed);
+        }
+
+        self.next += 1;
+        self.next
+    }
+
+    /// Pushes a frame on top of `slot`.
+    #[inline(always)]
+    fn push(&mut self, slot: usize, id: u64, parent: u64, deltas: [Delta; 2]) {
+        let frame = self.frames.get_mut(slot).assume();
+        frame.id = id;
+        frame.parent = parent;
+        frame.deltas = deltas;
+        frame.computed = [false; 2];
+    }
+
+    /// Computes the [`Accumulator`] of an [`Evaluator`].
+    #[inline(always)]
+    fn materialize(&mut self, e: &Evaluator) -> &Accumulator {
+        if self.generation != Nnue::generation() {
+            self.generation = Nnue::generation();
+            self.cache = vec![Entry::default(); Feature::BUCKETS];
+            for frame in &mut self.frames {
+                frame.computed = [false; 2];
+            }
+        }
+
+        if self.frames.get(e.slot).assume().id != e.id {
+            self.push(e.slot, e.id, 0, [Delta::Refresh; 2]);
+        }
+
+        for side in Color::iter() {
+            self.resolve(side, e.slot, &e.pos);
+        }
+
+        &self.frames.get(e.slot).assume().acc
+    }
+
+    /// Applies the pending updates since the nearest computed ancestor.
+    #[inline(always)]
+    fn resolve(&mut self, side: Color, slot: usize, pos: &Position) {
+        let mut k = slot;
+        for _ in 0..Self::LEN {
+            let frame = self.frames.get(k).assume();
+            if frame.computed[side as usize] {
+                break;
+            } else if frame.deltas[side as usize] == Delta::Refresh {
+                return self.refresh(side, slot, pos);
+            }
+
+            let parent = (k + Self::LEN - 1) % Self::LEN;
+            if self.frames.get(parent).assume().id != frame.parent {
+                return self.refresh(side, slot, pos);
+            }
+
+            k = parent;
+        }
+
+        if !self.frames.get(k).assume().computed[side as usize] {
+            return self.refresh(side, slot, pos);
+        }
+
+        while k != slot {
+            let next = (k + 1) % Self::LEN;
+            let [prev, frame] = self.frames.get_disjoint_mut([k, next]).assume();
+            frame.acc.copy(side, &prev.acc);
+            if let Delta::Update(sub, add) = frame.deltas[side as usize] {
+                frame.acc.update(side, sub, add);
+            }
+
+            frame.computed[side as usize] = true;
+            k = next;
+        }
+    }
+
+    /// Recomputes one side of the [`Accumulator`] from the cache.
+    #[inline(always)]
+    fn refresh(&mut self, side: Color, slot: usize, pos: &Position) {
+        let ksq = pos.king(side);
+        let entry = self.cache.get_mut(Feature::bucket(side, ksq)).assume();
+        let pieces = &mut entry.pieces[side as usize];
+
+        let mut sub = Piece::iter().flat_map(|p| {
+            let bb = pieces[p as usize] & !pos.board().by_piece(p);
+            bb.into_iter().map(move |sq| Feature::new(side, ksq, p, sq))
+        });
+
+        let mut add = Piece::iter().flat_map(|p| {
+            let bb = pos.board().by_piece(p) & !pieces[p as usize];
+            bb.into_iter().map(move |sq| Feature::new(side, ksq, p, sq))
+        });
+
+        loop {
+            match (sub.next(), add.next()) {
+                (None, None) => break,
+                (s1, a1) => entry.acc.update(side, [s1, None], [a1, None]),
+            }
+        }
+
+        for p in Piece::iter() {
+            pieces[p as usize] = pos.board().by_piece(p);
+        }
+
+        let frame = self.frames.get_mut(slot).assume();
+        frame.acc.copy(side, &entry.acc);
+        frame.computed[side as usize] = true;
+    }
+}
+
 /// An incrementally evaluated [`Position`].
-#[derive(Debug, Display, Clone, Eq, PartialEq, Hash, Deref)]
+///
+/// Moves only record how the [`Accumulator`] should be updated,
+/// which is deferred to a per-thread stack until the position is actually evaluated.
+#[derive(Debug, Display, Clone, Deref)]
 #[debug("Evaluator({self})")]
 #[display("{pos}")]
 pub struct Evaluator {
     #[deref]
     pos: Position,
-    acc: Accumulator,
+    id: u64,
+    slot: usize,
 }
 
 #[cfg(test)]
@@ -33,20 +216,25 @@ impl Default for Evaluator {
     }
 }
 
+impl Eq for Evaluator {}
+
+impl PartialEq for Evaluator {
+    fn eq(&self, other: &Self) -> bool {
+        self.pos == other.pos
+    }
+}
+
+impl Hash for Evaluator {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.pos.hash(state);
+    }
+}
+
 impl Evaluator {
     /// Constructs the evaluator from a [`Position`].
     pub fn new(pos: Position) -> Self {
-        let mut acc = Accumulator::default();
-
-        for side in Color::iter() {
-            let ksq = pos.king(side);
-            for (p, s) in pos.iter() {
-                let add = Feature::new(side, ksq, p, s);
-                acc.update(side, [None, None], [Some(add), None]);
-            }
-        }
-
-        Evaluator { pos, acc }
+        let id = Stack::with(|s| s.id());
+        Evaluator { pos, id, slot: 0 }
     }
 
     /// Play a [null-move].
@@ -60,20 +248,17 @@ impl Evaluator {
     pub fn play(&mut self, m: Move) {
         let turn = self.turn();
         let promotion = m.promotion();
-        let (wc, wt) = (m.whence(), m.whither());
+        let (wc, wt) = match m.castling_squares() {
+            None => (m.whence(), m.whither()),
+            Some((king, _)) => (m.whence(), king),
+        };
+
         let (role, capture) = self.pos.play(m);
         let mut sides = [Some(!turn), Some(turn)];
+        let mut deltas = [Delta::Refresh; 2];
 
-        if role == Role::King
-            && Feature::new(turn, wc, Piece::lower(), Square::lower())
-                != Feature::new(turn, wt, Piece::lower(), Square::lower())
-        {
+        if role == Role::King && Feature::bucket(turn, wc) != Feature::bucket(turn, wt) {
             sides[1] = None;
-            self.acc.refresh(turn);
-            for (p, s) in self.pos.iter() {
-                let add = Feature::new(turn, wt, p, s);
-                self.acc.update(turn, [None, None], [Some(add), None]);
-            }
         }
 
         for side in sides.into_iter().flatten() {
@@ -86,20 +271,22 @@ impl Evaluator {
             if let Some((r, sq)) = capture {
                 let victim = Piece::new(r, !turn);
                 sub[1] = Some(Feature::new(side, ksq, victim, sq));
-            } else if role == Role::King && (wt - wc).abs() == 2 {
+            } else if let Some((_, wr)) = m.castling_squares() {
                 let rook = Piece::new(Role::Rook, turn);
-                let (wc, wt) = if wt > wc {
-                    (Square::H1.perspective(turn), Square::F1.perspective(turn))
-                } else {
-                    (Square::A1.perspective(turn), Square::D1.perspective(turn))
-                };
-
-                sub[1] = Some(Feature::new(side, ksq, rook, wc));
-                add[1] = Some(Feature::new(side, ksq, rook, wt));
+                sub[1] = Some(Feature::new(side, ksq, rook, m.whither()));
+                add[1] = Some(Feature::new(side, ksq, rook, wr));
             }
 
-            self.acc.update(side, sub, add);
+            deltas[side as usize] = Delta::Update(sub, add);
         }
+
+        let parent = self.id;
+        self.slot = (self.slot + 1) % Stack::LEN;
+        self.id = Stack::with(|s| {
+            let id = s.id();
+            s.push(self.slot, id, parent, deltas);
+            id
+        });
     }
 
     /// Estimates the material gain of a move.
@@ -146,7 +333,7 @@ impl Evaluator {
     /// The [`Position`]'s evaluation.
     pub fn evaluate(&self) -> Value {
         let phase = (self.occupied().len() - 1) / 4;
-        let value = self.acc.evaluate(self.turn(), phase) / 128;
+        let value = Stack::with(|s| s.materialize(self).evaluate(self.turn(), phase)) / 128;
         value.saturate()
     }
 }
@@ -162,26 +349,122 @@ impl FromStr for Evaluator {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use proptest::sample::Selector;
-    use std::fmt::Debug;
+    use proptest::sample::{Selector, size_range};
+    use std::{fmt::Debug, thread};
     use test_strategy::proptest;
 
+    fn accumulator(e: &Evaluator) -> Accumulator {
+        Stack::with(|s| s.materialize(e).clone())
+    }
+
+    fn fresh(pos: &Position) -> Accumulator {
+        let mut acc = Accumulator::default();
+        for side in Color::iter() {
+            let ksq = pos.king(side);
+            for (p, s) in pos.iter() {
+                let add = Feature::new(side, ksq, p, s);
+                acc.update(side, [None, None], [Some(add), None]);
+            }
+        }
+
+        acc
+    }
+
     #[proptest]
     fn play_updates_evaluator(
         #[filter(#e.outcome().is_none())] mut e: Evaluator,
         #[map(|sq: Selector| sq.select(#e.moves().flatten()))] m: Move,
     ) {
+        assert_eq!(accumulator(&e), fresh(&e.pos));
+
         let mut pos = e.pos.clone();
         e.play(m);
         pos.play(m);
+        assert_eq!(accumulator(&e), fresh(&pos));
         assert_eq!(e, Evaluator::new(pos));
     }
 
+    #[proptest]
+    fn play_updates_evaluator_lazily(
+        #[filter(#e.outcome().is_none())] mut e: Evaluator,
+        #[any(size_range(1..16).lift())] selectors: Vec<(Selector, bool)>,
+    ) {
+        assert_eq!(accumulator(&e), fresh(&e.pos));
+
+        let mut pos = e.pos.clone();
+        for (s, evaluate) in selectors {
+            let Some(m) = s.try_select(e.moves().flatten()) else {
+                break;
+            };
+
+            e.play(m);
+            pos.play(m);
+            if evaluate {
+                assert_eq!(accumulator(&e), fresh(&pos));
+            }
+        }
+
+        assert_eq!(accumulator(&e), fresh(&pos));
+    }
+
+    #[proptest]
+    fn play_does_not_clobber_siblings(
+        #[filter(#e.outcome().is_none())] e: Evaluator,
+        #[map(|sq: Selector| sq.select(#e.moves().flatten()))] m: Move,
+        #[map(|sq: Selector| sq.select(#e.moves().flatten()))] n: Move,
+    ) {
+        assert_eq!(accumulator(&e), fresh(&e.pos));
+
+        let mut a = e.clone();
+        let mut b = e.clone();
+        a.play(m);
+        assert_eq!(accumulator(&a), fresh(&a.pos));
+        b.play(n);
+        assert_eq!(accumulator(&b), fresh(&b.pos));
+        assert_eq!(accumulator(&a), fresh(&a.pos));
+    }
+
+    #[proptest]
+    fn evaluator_can_be_evaluated_on_any_thread(
+        #[filter(#e.outcome().is_none())] mut e: Evaluator,
+        #[map(|sq: Selector| sq.select(#e.moves().flatten()))] m: Move,
+    ) {
+        e.play(m);
+        let value = e.evaluate();
+        assert_eq!(thread::spawn(move || e.evaluate()).join().ok(), Some(value));
+    }
+
+    #[test]
+    fn play_updates_evaluator_when_castling_in_chess960() {
+        let e: Evaluator = "rk5r/8/8/8/8/8/8/RK5R w HAha - 0 1".parse().unwrap();
+        for m in e.moves().filter(|ms| ms.is_castling()).flatten() {
+            let mut e = e.clone();
+            let mut pos = e.pos.clone();
+            e.play(m);
+            pos.play(m);
+            assert_eq!(accumulator(&e), fresh(&pos));
+            assert_eq!(e, Evaluator::new(pos));
+        }
+    }
+
+    #[test]
+    fn play_updates_evaluator_when_king_walks_across_buckets() {
+        let mut e: Evaluator = "4k3/8/8/8/8/8/3P4/4K3 w - - 0 1".parse().unwrap();
+        for m in [
+            "e1d1", "e8d8", "d1e1", "d8e8", "e1f2", "e8d7", "f2e1", "d7e8",
+        ] {
+            let m = e.moves().flatten().find(|n| n.to_string() == m).unwrap();
+            e.play(m);
+            assert_eq!(accumulator(&e), fresh(&e.pos));
+        }
+    }
+
     #[proptest]
     fn pass_updates_evaluator(#[filter(!#e.is_check())] mut e: Evaluator) {
         let mut pos = e.pos.clone();
         e.pass();
         pos.pass();
+        assert_eq!(accumulator(&e), fresh(&pos));
         assert_eq!(e, Evaluator::new(pos));
     }
 
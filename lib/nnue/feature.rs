@@ -29,6 +29,16 @@ impl Feature {
         6, 6, 7, 7, 7, 7, 6, 6,
     ];
 
+    /// The total number of different king buckets, counting mirrored buckets separately.
+    pub const BUCKETS: usize = 16;
+
+    /// The king bucket from some perspective.
+    #[inline(always)]
+    pub fn bucket(side: Color, ksq: Square) -> usize {
+        let mirrored = ksq.file() <= File::D;
+        2 * Self::KING_BUCKETS[ksq.perspective(side) as usize] as usize + mirrored as usize
+    }
+
     /// Constructs feature from some perspective.
     #[inline(always)]
     pub fn new(side: Color, ksq: Square, piece: Piece, sq: Square) -> Self {
@@ -53,6 +63,18 @@ mod tests {
         assert_eq!(Feature::LEN, Feature::iter().len());
     }
 
+    #[proptest]
+    fn features_are_equal_within_the_same_bucket(
+        c: Color,
+        k: Square,
+        #[filter(Feature::bucket(#c, #k) == Feature::bucket(#c, #l))] l: Square,
+        p: Piece,
+        sq: Square,
+    ) {
+        assert!(Feature::bucket(c, k) < Feature::BUCKETS);
+        assert_eq!(Feature::new(c, k, p, sq), Feature::new(c, l, p, sq));
+    }
+
     #[proptest]
     fn is_unique_to_perspective(c: Color, ksq: Square, p: Piece, sq: Square) {
         assert_ne!(Feature::new(c, ksq, p, sq), Feature::new(!c, ksq, p, sq));
@@ -12,35 +12,134 @@ pub struct Hidden<const N: usize> {
 
 impl<const N: usize> Hidden<N> {
     #[doc(hidden)]
-    #[inline(always)]
-    #[cfg(target_feature = "avx2")]
-    pub unsafe fn avx2(&self, us: &[i16; N], them: &[i16; N]) -> i32 {
+    #[inline]
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx512f,avx512bw,avx512vnni")]
+    pub unsafe fn avx512vnni(&self, us: &[i16; N], them: &[i16; N]) -> i32 {
         const { assert!(N % 128 == 0) }
 
         use crate::util::Assume;
         use std::{arch::x86_64::*, mem::transmute};
 
-        #[inline(always)]
-        unsafe fn sqrcrelu(p: __m256i, q: __m256i) -> __m256i {
-            unsafe {
-                let r = _mm256_packus_epi16(p, q);
-                let p = _mm256_unpacklo_epi8(r, _mm256_setzero_si256());
-                let q = _mm256_unpackhi_epi8(r, _mm256_setzero_si256());
-                let p = _mm256_slli_epi16(p, 3);
-                let q = _mm256_slli_epi16(q, 3);
-                let p = _mm256_mulhrs_epi16(p, p);
-                let q = _mm256_mulhrs_epi16(q, q);
-                let r = _mm256_packus_epi16(p, q);
-                _mm256_permute4x64_epi64(r, _MM_SHUFFLE(3, 1, 2, 0))
+        #[inline]
+        #[target_feature(enable = "avx512f,avx512bw")]
+        fn sqrcrelu(p: __m512i, q: __m512i) -> __m512i {
+            let r = _mm512_packus_epi16(p, q);
+            let p = _mm512_unpacklo_epi8(r, _mm512_setzero_si512());
+            let q = _mm512_unpackhi_epi8(r, _mm512_setzero_si512());
+            let p = _mm512_slli_epi16(p, 3);
+            let q = _mm512_slli_epi16(q, 3);
+            let p = _mm512_mulhrs_epi16(p, p);
+            let q = _mm512_mulhrs_epi16(q, q);
+            let r = _mm512_packus_epi16(p, q);
+            _mm512_permutexvar_epi64(_mm512_setr_epi64(0, 2, 4, 6, 1, 3, 5, 7), r)
+        }
+
+        #[inline]
+        #[target_feature(enable = "avx512f,avx512vnni")]
+        fn dot(p: __m512i, q: __m512i, r: __m512i) -> __m512i {
+            _mm512_dpbusd_epi32(r, p, q)
+        }
+
+        unsafe {
+            let mut y = _mm512_setzero_si512();
+
+            for (w, i) in self.weight.iter().zip([us, them]) {
+                (w.as_ptr() as usize % 64 == 0).assume();
+                (i.as_ptr() as usize % 64 == 0).assume();
+
+                for (a, x) in Iterator::zip(w.as_chunks::<128>().0.iter(), i.as_chunks::<128>().0.iter()) {
+                    let a = transmute::<&[i8; 128], &[__m512i; 2]>(a);
+                    let x = transmute::<&[i16; 128], &[[__m512i; 2]; 2]>(x);
+
+                    y = dot(sqrcrelu(x[0][0], x[0][1]), a[0], y);
+                    y = dot(sqrcrelu(x[1][0], x[1][1]), a[1], y);
+                }
             }
+
+            self.bias + _mm512_reduce_add_epi32(y)
         }
+    }
 
-        #[inline(always)]
-        unsafe fn dot(p: __m256i, q: __m256i, r: __m256i) -> __m256i {
-            unsafe {
-                let s = _mm256_maddubs_epi16(p, q);
-                _mm256_add_epi32(r, _mm256_madd_epi16(s, _mm256_set1_epi16(1)))
+    #[doc(hidden)]
+    #[inline]
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx512f,avx512bw")]
+    pub unsafe fn avx512(&self, us: &[i16; N], them: &[i16; N]) -> i32 {
+        const { assert!(N % 128 == 0) }
+
+        use crate::util::Assume;
+        use std::{arch::x86_64::*, mem::transmute};
+
+        #[inline]
+        #[target_feature(enable = "avx512f,avx512bw")]
+        fn sqrcrelu(p: __m512i, q: __m512i) -> __m512i {
+            let r = _mm512_packus_epi16(p, q);
+            let p = _mm512_unpacklo_epi8(r, _mm512_setzero_si512());
+            let q = _mm512_unpackhi_epi8(r, _mm512_setzero_si512());
+            let p = _mm512_slli_epi16(p, 3);
+            let q = _mm512_slli_epi16(q, 3);
+            let p = _mm512_mulhrs_epi16(p, p);
+            let q = _mm512_mulhrs_epi16(q, q);
+            let r = _mm512_packus_epi16(p, q);
+            _mm512_permutexvar_epi64(_mm512_setr_epi64(0, 2, 4, 6, 1, 3, 5, 7), r)
+        }
+
+        #[inline]
+        #[target_feature(enable = "avx512f,avx512bw")]
+        fn dot(p: __m512i, q: __m512i, r: __m512i) -> __m512i {
+            let s = _mm512_maddubs_epi16(p, q);
+            _mm512_add_epi32(r, _mm512_madd_epi16(s, _mm512_set1_epi16(1)))
+        }
+
+        unsafe {
+            let mut y = _mm512_setzero_si512();
+
+            for (w, i) in self.weight.iter().zip([us, them]) {
+                (w.as_ptr() as usize % 64 == 0).assume();
+                (i.as_ptr() as usize % 64 == 0).assume();
+
+                for (a, x) in Iterator::zip(w.as_chunks::<128>().0.iter(), i.as_chunks::<128>().0.iter()) {
+                    let a = transmute::<&[i8; 128], &[__m512i; 2]>(a);
+                    let x = transmute::<&[i16; 128], &[[__m512i; 2]; 2]>(x);
+
+                    y = dot(sqrcrelu(x[0][0], x[0][1]), a[0], y);
+                    y = dot(sqrcrelu(x[1][0], x[1][1]), a[1], y);
+                }
             }
+
+            self.bias + _mm512_reduce_add_epi32(y)
+        }
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2,avxvnni")]
+    pub unsafe fn avxvnni(&self, us: &[i16; N], them: &[i16; N]) -> i32 {
+        const { assert!(N % 128 == 0) }
+
+        use crate::util::Assume;
+        use std::{arch::x86_64::*, mem::transmute};
+
+        #[inline]
+        #[target_feature(enable = "avx2")]
+        fn sqrcrelu(p: __m256i, q: __m256i) -> __m256i {
+            let r = _mm256_packus_epi16(p, q);
+            let p = _mm256_unpacklo_epi8(r, _mm256_setzero_si256());
+            let q = _mm256_unpackhi_epi8(r, _mm256_setzero_si256());
+            let p = _mm256_slli_epi16(p, 3);
+            let q = _mm256_slli_epi16(q, 3);
+            let p = _mm256_mulhrs_epi16(p, p);
+            let q = _mm256_mulhrs_epi16(q, q);
+            let r = _mm256_packus_epi16(p, q);
+            _mm256_permute4x64_epi64(r, _MM_SHUFFLE(3, 1, 2, 0))
+        }
+
+        #[inline]
+        #[target_feature(enable = "avxvnni")]
+        fn dot(p: __m256i, q: __m256i, r: __m256i) -> __m256i {
+            _mm256_dpbusd_avx_epi32(r, p, q)
         }
 
         unsafe {
@@ -50,7 +149,7 @@ impl<const N: usize> Hidden<N> {
                 (w.as_ptr() as usize % 32 == 0).assume();
                 (i.as_ptr() as usize % 32 == 0).assume();
 
-                for (a, x) in Iterator::zip(w.array_chunks::<128>(), i.array_chunks::<128>()) {
+                for (a, x) in Iterator::zip(w.as_chunks::<128>().0.iter(), i.as_chunks::<128>().0.iter()) {
                     let a = transmute::<&[i8; 128], &[__m256i; 4]>(a);
                     let x = transmute::<&[i16; 128], &[[__m256i; 2]; 4]>(x);
 
@@ -74,34 +173,94 @@ impl<const N: usize> Hidden<N> {
     }
 
     #[doc(hidden)]
-    #[inline(always)]
-    #[cfg(target_feature = "ssse3")]
+    #[inline]
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn avx2(&self, us: &[i16; N], them: &[i16; N]) -> i32 {
+        const { assert!(N % 128 == 0) }
+
+        use crate::util::Assume;
+        use std::{arch::x86_64::*, mem::transmute};
+
+        #[inline]
+        #[target_feature(enable = "avx2")]
+        fn sqrcrelu(p: __m256i, q: __m256i) -> __m256i {
+            let r = _mm256_packus_epi16(p, q);
+            let p = _mm256_unpacklo_epi8(r, _mm256_setzero_si256());
+            let q = _mm256_unpackhi_epi8(r, _mm256_setzero_si256());
+            let p = _mm256_slli_epi16(p, 3);
+            let q = _mm256_slli_epi16(q, 3);
+            let p = _mm256_mulhrs_epi16(p, p);
+            let q = _mm256_mulhrs_epi16(q, q);
+            let r = _mm256_packus_epi16(p, q);
+            _mm256_permute4x64_epi64(r, _MM_SHUFFLE(3, 1, 2, 0))
+        }
+
+        #[inline]
+        #[target_feature(enable = "avx2")]
+        fn dot(p: __m256i, q: __m256i, r: __m256i) -> __m256i {
+            let s = _mm256_maddubs_epi16(p, q);
+            _mm256_add_epi32(r, _mm256_madd_epi16(s, _mm256_set1_epi16(1)))
+        }
+
+        unsafe {
+            let mut y = _mm256_setr_epi32(self.bias, 0, 0, 0, 0, 0, 0, 0);
+
+            for (w, i) in self.weight.iter().zip([us, them]) {
+                (w.as_ptr() as usize % 32 == 0).assume();
+                (i.as_ptr() as usize % 32 == 0).assume();
+
+                for (a, x) in Iterator::zip(w.as_chunks::<128>().0.iter(), i.as_chunks::<128>().0.iter()) {
+                    let a = transmute::<&[i8; 128], &[__m256i; 4]>(a);
+                    let x = transmute::<&[i16; 128], &[[__m256i; 2]; 4]>(x);
+
+                    y = dot(sqrcrelu(x[0][0], x[0][1]), a[0], y);
+                    y = dot(sqrcrelu(x[1][0], x[1][1]), a[1], y);
+                    y = dot(sqrcrelu(x[2][0], x[2][1]), a[2], y);
+                    y = dot(sqrcrelu(x[3][0], x[3][1]), a[3], y);
+                }
+            }
+
+            // https://stackoverflow.com/a/60109639
+            let r = _mm256_castsi256_si128(y);
+            let s = _mm256_extracti128_si256(y, 1);
+            let r = _mm_add_epi32(r, s);
+            let s = _mm_unpackhi_epi64(r, r);
+            let r = _mm_add_epi32(r, s);
+            let s = _mm_shuffle_epi32(r, _MM_SHUFFLE(2, 3, 0, 1));
+            let r = _mm_add_epi32(r, s);
+            _mm_extract_epi32(r, 0)
+        }
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "ssse3")]
     pub unsafe fn sse(&self, us: &[i16; N], them: &[i16; N]) -> i32 {
         const { assert!(N % 64 == 0) }
 
         use crate::util::Assume;
         use std::{arch::x86_64::*, mem::transmute};
 
-        #[inline(always)]
-        unsafe fn sqrcrelu(p: __m128i, q: __m128i) -> __m128i {
-            unsafe {
-                let r = _mm_packus_epi16(p, q);
-                let p = _mm_unpacklo_epi8(r, _mm_setzero_si128());
-                let q = _mm_unpackhi_epi8(r, _mm_setzero_si128());
-                let p = _mm_slli_epi16(p, 3);
-                let q = _mm_slli_epi16(q, 3);
-                let p = _mm_mulhrs_epi16(p, p);
-                let q = _mm_mulhrs_epi16(q, q);
-                _mm_packus_epi16(p, q)
-            }
+        #[inline]
+        #[target_feature(enable = "ssse3")]
+        fn sqrcrelu(p: __m128i, q: __m128i) -> __m128i {
+            let r = _mm_packus_epi16(p, q);
+            let p = _mm_unpacklo_epi8(r, _mm_setzero_si128());
+            let q = _mm_unpackhi_epi8(r, _mm_setzero_si128());
+            let p = _mm_slli_epi16(p, 3);
+            let q = _mm_slli_epi16(q, 3);
+            let p = _mm_mulhrs_epi16(p, p);
+            let q = _mm_mulhrs_epi16(q, q);
+            _mm_packus_epi16(p, q)
         }
 
-        #[inline(always)]
-        unsafe fn dot(p: __m128i, q: __m128i, r: __m128i) -> __m128i {
-            unsafe {
-                let s = _mm_maddubs_epi16(p, q);
-                _mm_add_epi32(r, _mm_madd_epi16(s, _mm_set1_epi16(1)))
-            }
+        #[inline]
+        #[target_feature(enable = "ssse3")]
+        fn dot(p: __m128i, q: __m128i, r: __m128i) -> __m128i {
+            let s = _mm_maddubs_epi16(p, q);
+            _mm_add_epi32(r, _mm_madd_epi16(s, _mm_set1_epi16(1)))
         }
 
         unsafe {
@@ -111,7 +270,7 @@ impl<const N: usize> Hidden<N> {
                 (w.as_ptr() as usize % 16 == 0).assume();
                 (i.as_ptr() as usize % 16 == 0).assume();
 
-                for (a, x) in Iterator::zip(w.array_chunks::<64>(), i.array_chunks::<64>()) {
+                for (a, x) in Iterator::zip(w.as_chunks::<64>().0.iter(), i.as_chunks::<64>().0.iter()) {
                     let a = transmute::<&[i8; 64], &[__m128i; 4]>(a);
                     let x = transmute::<&[i16; 64], &[[__m128i; 2]; 4]>(x);
 
@@ -132,15 +291,17 @@ impl<const N: usize> Hidden<N> {
     }
 
     #[doc(hidden)]
-    #[inline(always)]
-    #[cfg(target_feature = "neon")]
+    #[inline]
+    #[cfg(target_arch = "aarch64")]
+    #[target_feature(enable = "neon,dotprod")]
     pub unsafe fn neon(&self, us: &[i16; N], them: &[i16; N]) -> i32 {
         const { assert!(N % 64 == 0) }
 
         use crate::util::Assume;
         use std::{arch::aarch64::*, mem::transmute};
 
-        #[inline(always)]
+        #[inline]
+        #[target_feature(enable = "neon")]
         unsafe fn sqrcrelu(p: int16x8_t, q: int16x8_t) -> uint8x16_t {
             unsafe {
                 let p = vmovl_u8(vqmovun_s16(p));
@@ -153,7 +314,8 @@ impl<const N: usize> Hidden<N> {
             }
         }
 
-        #[inline(always)]
+        #[inline]
+        #[target_feature(enable = "neon,dotprod")]
         unsafe fn dot(p: uint8x16_t, q: int8x16_t, r: int32x4_t) -> int32x4_t {
             unsafe { vdotq_s32(r, transmute(p), q) }
         }
@@ -165,7 +327,7 @@ impl<const N: usize> Hidden<N> {
                 (w.as_ptr() as usize % 16 == 0).assume();
                 (i.as_ptr() as usize % 16 == 0).assume();
 
-                for (a, x) in Iterator::zip(w.array_chunks::<64>(), i.array_chunks::<64>()) {
+                for (a, x) in Iterator::zip(w.as_chunks::<64>().0.iter(), i.as_chunks::<64>().0.iter()) {
                     let a = transmute::<&[i8; 64], &[int8x16_t; 4]>(a);
                     let x = transmute::<&[i16; 64], &[[int16x8_t; 2]; 4]>(x);
 
@@ -194,30 +356,81 @@ impl<const N: usize> Hidden<N> {
     }
 }
 
-impl<const N: usize> Hidden<N> {
-    /// Transforms the accumulator.
-    #[inline(always)]
-    pub fn forward(&self, us: &[i16; N], them: &[i16; N]) -> i32 {
-        #[cfg(target_feature = "avx2")]
-        unsafe {
-            self.avx2(us, them)
+/// A kernel that transforms the accumulator.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Kernel {
+    Scalar,
+    #[cfg(target_arch = "x86_64")]
+    Sse,
+    #[cfg(target_arch = "x86_64")]
+    Avx2,
+    #[cfg(target_arch = "x86_64")]
+    Avxvnni,
+    #[cfg(target_arch = "x86_64")]
+    Avx512,
+    #[cfg(target_arch = "x86_64")]
+    Avx512vnni,
+    #[cfg(target_arch = "aarch64")]
+    Neon,
+}
+
+impl Kernel {
+    /// The fastest kernel supported by the CPU.
+    #[cold]
+    #[inline(never)]
+    pub fn detect() -> Self {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx512vnni") && is_x86_feature_detected!("avx512bw") {
+                return Kernel::Avx512vnni;
+            } else if is_x86_feature_detected!("avx512bw") {
+                return Kernel::Avx512;
+            } else if is_x86_feature_detected!("avxvnni") {
+                return Kernel::Avxvnni;
+            } else if is_x86_feature_detected!("avx2") {
+                return Kernel::Avx2;
+            } else if is_x86_feature_detected!("ssse3") {
+                return Kernel::Sse;
+            }
         }
 
-        #[cfg(not(target_feature = "avx2"))]
-        #[cfg(target_feature = "ssse3")]
-        unsafe {
-            self.sse(us, them)
+        #[cfg(target_arch = "aarch64")]
+        if std::arch::is_aarch64_feature_detected!("dotprod") {
+            return Kernel::Neon;
         }
 
-        #[cfg(target_feature = "neon")]
+        Kernel::Scalar
+    }
+}
+
+impl<const N: usize> Hidden<N> {
+    /// Transforms the accumulator with a [`Kernel`].
+    ///
+    /// Dispatching on the kernel rather than calling through a function pointer
+    /// lets the kernel be inlined whenever the target supports it.
+    ///
+    /// # Safety
+    ///
+    /// The kernel must be supported by the CPU.
+    #[inline(always)]
+    pub unsafe fn forward(&self, kernel: Kernel, us: &[i16; N], them: &[i16; N]) -> i32 {
         unsafe {
-            self.neon(us, them)
+            match kernel {
+                #[cfg(target_arch = "x86_64")]
+                Kernel::Avx512vnni => self.avx512vnni(us, them),
+                #[cfg(target_arch = "x86_64")]
+                Kernel::Avx512 => self.avx512(us, them),
+                #[cfg(target_arch = "x86_64")]
+                Kernel::Avxvnni => self.avxvnni(us, them),
+                #[cfg(target_arch = "x86_64")]
+                Kernel::Avx2 => self.avx2(us, them),
+                #[cfg(target_arch = "x86_64")]
+                Kernel::Sse => self.sse(us, them),
+                #[cfg(target_arch = "aarch64")]
+                Kernel::Neon => self.neon(us, them),
+                Kernel::Scalar => self.scalar(us, them),
+            }
         }
-
-        #[cfg(not(target_feature = "avx2"))]
-        #[cfg(not(target_feature = "ssse3"))]
-        #[cfg(not(target_feature = "neon"))]
-        self.scalar(us, them)
     }
 }
 
@@ -226,21 +439,60 @@ mod tests {
     use super::*;
     use test_strategy::proptest;
 
-    #[cfg(target_feature = "avx2")]
+    #[cfg(target_arch = "x86_64")]
+    #[proptest]
+    fn uses_avx512vnni(o: Hidden<128>, i: AlignTo64<[[i16; 128]; 2]>) {
+        if is_x86_feature_detected!("avx512vnni") && is_x86_feature_detected!("avx512bw") {
+            assert_eq!(
+                unsafe { o.avx512vnni(&i[0], &i[1]) },
+                o.scalar(&i[0], &i[1])
+            );
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[proptest]
+    fn uses_avx512(o: Hidden<128>, i: AlignTo64<[[i16; 128]; 2]>) {
+        if is_x86_feature_detected!("avx512bw") {
+            assert_eq!(unsafe { o.avx512(&i[0], &i[1]) }, o.scalar(&i[0], &i[1]));
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[proptest]
+    fn uses_avxvnni(o: Hidden<128>, i: AlignTo64<[[i16; 128]; 2]>) {
+        if is_x86_feature_detected!("avxvnni") {
+            assert_eq!(unsafe { o.avxvnni(&i[0], &i[1]) }, o.scalar(&i[0], &i[1]));
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
     #[proptest]
     fn uses_avx(o: Hidden<128>, i: AlignTo64<[[i16; 128]; 2]>) {
-        assert_eq!(unsafe { o.avx2(&i[0], &i[1]) }, o.scalar(&i[0], &i[1]));
+        if is_x86_feature_detected!("avx2") {
+            assert_eq!(unsafe { o.avx2(&i[0], &i[1]) }, o.scalar(&i[0], &i[1]));
+        }
     }
 
-    #[cfg(target_feature = "ssse3")]
+    #[cfg(target_arch = "x86_64")]
     #[proptest]
     fn uses_sse(o: Hidden<128>, i: AlignTo64<[[i16; 128]; 2]>) {
-        assert_eq!(unsafe { o.sse(&i[0], &i[1]) }, o.scalar(&i[0], &i[1]));
+        if is_x86_feature_detected!("ssse3") {
+            assert_eq!(unsafe { o.sse(&i[0], &i[1]) }, o.scalar(&i[0], &i[1]));
+        }
     }
 
-    #[cfg(target_feature = "neon")]
+    #[cfg(target_arch = "aarch64")]
     #[proptest]
     fn uses_neon(o: Hidden<128>, i: AlignTo64<[[i16; 128]; 2]>) {
-        assert_eq!(unsafe { o.neon(&i[0], &i[1]) }, o.scalar(&i[0], &i[1]));
+        if std::arch::is_aarch64_feature_detected!("dotprod") {
+            assert_eq!(unsafe { o.neon(&i[0], &i[1]) }, o.scalar(&i[0], &i[1]));
+        }
+    }
+
+    #[proptest]
+    fn kernel_matches_scalar(o: Hidden<128>, i: AlignTo64<[[i16; 128]; 2]>) {
+        let kernel = Kernel::detect();
+        assert_eq!(unsafe { o.forward(kernel, &i[0], &i[1]) }, o.scalar(&i[0], &i[1]));
     }
 }
@@ -1,4 +1,4 @@
-use crate::nnue::Feature;
+use crate::nnue::{Feature, Nnue};
 use crate::util::{AlignTo64, Assume, Integer};
 use derive_more::with_trait::{Deref, DerefMut};
 use std::ops::{Add, AddAssign, Sub, SubAssign};
@@ -9,6 +9,161 @@ use proptest::{prelude::*, sample::Index};
 #[cfg(test)]
 use std::ops::Range;
 
+/// The type of the values in an accumulator.
+pub trait Element:
+    Default + Copy + Add<Output = Self> + AddAssign + Sub<Output = Self> + SubAssign
+{
+    /// Adds some rows of weights to the accumulator and subtracts others.
+    #[inline(always)]
+    fn update<const N: usize, const A: usize, const S: usize>(
+        add: [&[Self; N]; A],
+        sub: [&[Self; N]; S],
+        accumulator: &mut [Self; N],
+    ) {
+        scalar(add, sub, accumulator)
+    }
+}
+
+impl Element for i32 {}
+
+impl Element for i16 {
+    /// Adds some rows of weights to the accumulator and subtracts others.
+    ///
+    /// Dispatches to the [`Update`] kernel selected at startup.
+    #[inline(always)]
+    fn update<const N: usize, const A: usize, const S: usize>(
+        add: [&[Self; N]; A],
+        sub: [&[Self; N]; S],
+        accumulator: &mut [Self; N],
+    ) {
+        match Nnue::update() {
+            #[cfg(target_arch = "x86_64")]
+            Update::Avx512 if N % 32 == 0 => unsafe { avx512(add, sub, accumulator) },
+            #[cfg(target_arch = "x86_64")]
+            Update::Avx2 if N % 16 == 0 => unsafe { avx2(add, sub, accumulator) },
+            _ => scalar(add, sub, accumulator),
+        }
+    }
+}
+
+/// A kernel that updates the accumulator.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Update {
+    Scalar,
+    #[cfg(target_arch = "x86_64")]
+    Avx2,
+    #[cfg(target_arch = "x86_64")]
+    Avx512,
+}
+
+impl Update {
+    /// The fastest kernel supported by the CPU.
+    #[cold]
+    #[inline(never)]
+    pub fn kernel() -> Self {
+        #[cfg(target_arch = "x86_64")]
+        if is_x86_feature_detected!("avx512bw") {
+            return Update::Avx512;
+        }
+
+        #[cfg(target_arch = "x86_64")]
+        if is_x86_feature_detected!("avx2") {
+            return Update::Avx2;
+        }
+
+        Update::Scalar
+    }
+}
+
+#[inline]
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx512f,avx512bw")]
+unsafe fn avx512<const N: usize, const A: usize, const S: usize>(
+    add: [&[i16; N]; A],
+    sub: [&[i16; N]; S],
+    accumulator: &mut [i16; N],
+) {
+    use std::{arch::x86_64::*, mem::transmute};
+
+    (N % 32 == 0).assume();
+    (accumulator.as_ptr() as usize % 64 == 0).assume();
+    for w in add.iter().chain(&sub) {
+        (w.as_ptr() as usize % 64 == 0).assume();
+    }
+
+    unsafe {
+        for (i, y) in accumulator.as_chunks_mut::<32>().0.iter_mut().enumerate() {
+            let y = transmute::<&mut [i16; 32], &mut __m512i>(y);
+
+            let mut r = *y;
+            for w in add {
+                let a = transmute::<&[i16; 32], &__m512i>(w[i * 32..].first_chunk().assume());
+                r = _mm512_add_epi16(r, *a);
+            }
+
+            for w in sub {
+                let s = transmute::<&[i16; 32], &__m512i>(w[i * 32..].first_chunk().assume());
+                r = _mm512_sub_epi16(r, *s);
+            }
+
+            *y = r;
+        }
+    }
+}
+
+#[inline]
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn avx2<const N: usize, const A: usize, const S: usize>(
+    add: [&[i16; N]; A],
+    sub: [&[i16; N]; S],
+    accumulator: &mut [i16; N],
+) {
+    use std::{arch::x86_64::*, mem::transmute};
+
+    (N % 16 == 0).assume();
+    (accumulator.as_ptr() as usize % 64 == 0).assume();
+    for w in add.iter().chain(&sub) {
+        (w.as_ptr() as usize % 64 == 0).assume();
+    }
+
+    unsafe {
+        for (i, y) in accumulator.as_chunks_mut::<16>().0.iter_mut().enumerate() {
+            let y = transmute::<&mut [i16; 16], &mut __m256i>(y);
+
+            let mut r = *y;
+            for w in add {
+                let a = transmute::<&[i16; 16], &__m256i>(w[i * 16..].first_chunk().assume());
+                r = _mm256_add_epi16(r, *a);
+            }
+
+            for w in sub {
+                let s = transmute::<&[i16; 16], &__m256i>(w[i * 16..].first_chunk().assume());
+                r = _mm256_sub_epi16(r, *s);
+            }
+
+            *y = r;
+        }
+    }
+}
+
+#[inline(always)]
+fn scalar<T: Element, const N: usize, const A: usize, const S: usize>(
+    add: [&[T; N]; A],
+    sub: [&[T; N]; S],
+    accumulator: &mut [T; N],
+) {
+    for (i, y) in accumulator.iter_mut().enumerate() {
+        for w in add {
+            *y += w[i];
+        }
+
+        for w in sub {
+            *y -= w[i];
+        }
+    }
+}
+
 /// A linear feature transformer.
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Deref, DerefMut)]
 pub struct Linear<T, const N: usize> {
@@ -38,7 +193,7 @@ impl<const N: usize> Arbitrary for Box<Linear<i16, N>> {
 
 impl<T, const N: usize> Linear<T, N>
 where
-    T: Default + Copy + Add<Output = T> + AddAssign + Sub<Output = T> + SubAssign,
+    T: Element,
 {
     /// A fresh accumulator.
     #[inline(always)]
@@ -50,9 +205,14 @@ where
     #[inline(always)]
     pub fn add(&self, a1: Feature, accumulator: &mut [T; N]) {
         let a1 = self.weight.get(a1.cast::<usize>()).assume();
-        for (i, y) in accumulator.iter_mut().enumerate() {
-            *y += a1[i];
-        }
+        T::update([a1], [], accumulator);
+    }
+
+    /// Updates the accumulator by removing a feature.
+    #[inline(always)]
+    pub fn sub(&self, s1: Feature, accumulator: &mut [T; N]) {
+        let s1 = self.weight.get(s1.cast::<usize>()).assume();
+        T::update([], [s1], accumulator);
     }
 
     /// Updates the accumulator by removing a feature and adding another.
@@ -60,9 +220,7 @@ where
     pub fn sub_add(&self, s1: Feature, a1: Feature, accumulator: &mut [T; N]) {
         let s1 = self.weight.get(s1.cast::<usize>()).assume();
         let a1 = self.weight.get(a1.cast::<usize>()).assume();
-        for (i, y) in accumulator.iter_mut().enumerate() {
-            *y += a1[i] - s1[i];
-        }
+        T::update([a1], [s1], accumulator);
     }
 
     /// Updates the accumulator by removing two features and adding one other.
@@ -71,9 +229,7 @@ where
         let s1 = self.weight.get(s1.cast::<usize>()).assume();
         let s2 = self.weight.get(s2.cast::<usize>()).assume();
         let a1 = self.weight.get(a1.cast::<usize>()).assume();
-        for (i, y) in accumulator.iter_mut().enumerate() {
-            *y += a1[i] - s1[i] - s2[i];
-        }
+        T::update([a1], [s1, s2], accumulator);
     }
 
     /// Updates the accumulator by removing two features and adding two others.
@@ -90,9 +246,7 @@ where
         let s2 = self.weight.get(s2.cast::<usize>()).assume();
         let a1 = self.weight.get(a1.cast::<usize>()).assume();
         let a2 = self.weight.get(a2.cast::<usize>()).assume();
-        for (i, y) in accumulator.iter_mut().enumerate() {
-            *y += a1[i] - s1[i] + a2[i] - s2[i];
-        }
+        T::update([a1, a2], [s1, s2], accumulator);
     }
 }
 
@@ -128,7 +282,7 @@ impl<const N: usize> Arbitrary for Box<Affine<i16, N>> {
 
 impl<T, const N: usize> Affine<T, N>
 where
-    T: Default + Copy + Add<Output = T> + AddAssign + Sub<Output = T> + SubAssign,
+    T: Element,
 {
     /// A fresh accumulator.
     #[inline(always)]
@@ -143,6 +297,77 @@ mod tests {
     use proptest::array::uniform3;
     use test_strategy::proptest;
 
+    #[cfg(target_arch = "x86_64")]
+    #[proptest]
+    fn uses_avx512(
+        #[any(-128i16..128)] t: Box<Linear<i16, 64>>,
+        a1: Feature,
+        a2: Feature,
+        s1: Feature,
+        s2: Feature,
+        #[map(|vs: [i8; 64]| AlignTo64(vs.map(i16::from)))] prev: AlignTo64<[i16; 64]>,
+    ) {
+        if is_x86_feature_detected!("avx512bw") {
+            let [a1, a2, s1, s2] = [a1, a2, s1, s2].map(|f| &t[f.cast::<usize>()]);
+
+            let mut new = prev;
+            let mut expected = prev;
+            unsafe { avx512([a1, a2], [s1, s2], &mut new) };
+            scalar([a1, a2], [s1, s2], &mut expected);
+            assert_eq!(new, expected);
+
+            let mut new = prev;
+            let mut expected = prev;
+            unsafe { avx512([a1], [s1], &mut new) };
+            scalar([a1], [s1], &mut expected);
+            assert_eq!(new, expected);
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[proptest]
+    fn uses_avx2(
+        #[any(-128i16..128)] t: Box<Linear<i16, 64>>,
+        a1: Feature,
+        a2: Feature,
+        s1: Feature,
+        s2: Feature,
+        #[map(|vs: [i8; 64]| AlignTo64(vs.map(i16::from)))] prev: AlignTo64<[i16; 64]>,
+    ) {
+        if is_x86_feature_detected!("avx2") {
+            let [a1, a2, s1, s2] = [a1, a2, s1, s2].map(|f| &t[f.cast::<usize>()]);
+
+            let mut new = prev;
+            let mut expected = prev;
+            unsafe { avx2([a1, a2], [s1, s2], &mut new) };
+            scalar([a1, a2], [s1, s2], &mut expected);
+            assert_eq!(new, expected);
+
+            let mut new = prev;
+            let mut expected = prev;
+            unsafe { avx2([a1], [s1], &mut new) };
+            scalar([a1], [s1], &mut expected);
+            assert_eq!(new, expected);
+        }
+    }
+
+    #[proptest]
+    fn update_matches_scalar(
+        #[any(-128i16..128)] t: Box<Linear<i16, 64>>,
+        a1: Feature,
+        s1: Feature,
+        s2: Feature,
+        #[map(|vs: [i8; 64]| AlignTo64(vs.map(i16::from)))] prev: AlignTo64<[i16; 64]>,
+    ) {
+        let [a1, s1, s2] = [a1, s1, s2].map(|f| &t[f.cast::<usize>()]);
+
+        let mut new = prev;
+        let mut expected = prev;
+        i16::update([a1], [s1, s2], &mut new);
+        scalar([a1], [s1, s2], &mut expected);
+        assert_eq!(new, expected);
+    }
+
     #[proptest]
     fn fresh_accumulator_equals_bias(#[any(-128i16..128)] t: Box<Affine<i16, 2>>) {
         assert_eq!(t.fresh(), *t.bias);
@@ -161,6 +386,19 @@ mod tests {
         assert_eq!(new, [prev[0] + a1[0], prev[1] + a1[1], prev[2] + a1[2]]);
     }
 
+    #[proptest]
+    fn sub_updates_accumulator(
+        #[any(-128i16..128)] t: Box<Affine<i16, 3>>,
+        s1: Feature,
+        #[strategy(uniform3(-128..128i16))] prev: [i16; 3],
+    ) {
+        let mut new = prev;
+        t.sub(s1, &mut new);
+
+        let s1 = t.weight[s1.cast::<usize>()];
+        assert_eq!(new, [prev[0] - s1[0], prev[1] - s1[1], prev[2] - s1[2]]);
+    }
+
     #[proptest]
     fn add_sub_updates_accumulator(
         #[any(-128..128i16)] t: Box<Affine<i16, 3>>,
@@ -1,3 +1,4 @@
+mod budget;
 mod continuation;
 mod control;
 mod depth;
@@ -13,7 +14,9 @@ mod pv;
 mod rating;
 mod score;
 mod transposition;
+mod wdl;
 
+pub use budget::*;
 pub use continuation::*;
 pub use control::*;
 pub use depth::*;
@@ -29,3 +32,4 @@ pub use pv::*;
 pub use rating::*;
 pub use score::*;
 pub use transposition::*;
+pub use wdl::*;
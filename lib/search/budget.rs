@@ -0,0 +1,150 @@
+use crate::{chess::Move, search::Score, util::Integer};
+use std::{ops::Range, time::Duration};
+
+/// The time allotted to the search.
+///
+/// The soft limit is extended while the best move is unstable, the score is dropping,
+/// or the best move takes up a small fraction of the nodes searched, and cut short otherwise.
+/// It never exceeds the hard limit though.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Budget {
+    base: Duration,
+    soft: Duration,
+    hard: Duration,
+    stability: u8,
+    best: Option<(Move, Score)>,
+}
+
+impl Budget {
+    /// Constructs a budget with the given soft and hard limits.
+    #[inline(always)]
+    pub fn new(time: Range<Duration>) -> Self {
+        Budget {
+            base: time.start,
+            soft: time.start.min(time.end),
+            hard: time.end,
+            stability: 0,
+            best: None,
+        }
+    }
+
+    /// The time after which no new iteration should be started.
+    #[inline(always)]
+    pub fn soft(&self) -> Duration {
+        self.soft
+    }
+
+    /// The time after which the search must be interrupted.
+    #[inline(always)]
+    pub fn hard(&self) -> Duration {
+        self.hard
+    }
+
+    /// Adjusts the soft limit to the outcome of the last iteration.
+    ///
+    /// The `effort` is the fraction of nodes spent searching the `best` move.
+    pub fn update(&mut self, best: Move, score: Score, effort: f64) {
+        let drop = match self.best {
+            None => 0,
+            Some((m, s)) => {
+                self.stability = if m == best {
+                    (self.stability + 1).min(8)
+                } else {
+                    0
+                };
+                (s - score).get().clamp(0, 50)
+            }
+        };
+
+        let stability = 1.4 - self.stability as f64 / 10.;
+        let trend = 1. + drop as f64 / 100.;
+        let effort = 1.5 - effort.clamp(0., 1.);
+        let scale = (stability * trend * effort).clamp(0.25, 2.5);
+
+        self.soft = self.base.mul_f64(scale).min(self.hard);
+        self.best = Some((best, score));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::sample::size_range;
+    use test_strategy::proptest;
+
+    #[proptest]
+    fn soft_limit_never_exceeds_hard_limit(
+        #[strategy(..1000u64)]
+        #[map(Duration::from_millis)]
+        s: Duration,
+        #[strategy(..1000u64)]
+        #[map(Duration::from_millis)]
+        h: Duration,
+        #[any(size_range(..10).lift())] updates: Vec<(Move, Score, f64)>,
+    ) {
+        let mut b = Budget::new(s..h);
+        assert!(b.soft() <= b.hard());
+
+        for (m, score, effort) in updates {
+            b.update(m, score, effort);
+            assert!(b.soft() <= b.hard());
+            assert_eq!(b.hard(), h);
+        }
+    }
+
+    #[proptest]
+    fn soft_limit_shrinks_as_best_move_stabilizes(
+        #[strategy(..1000u64)]
+        #[map(Duration::from_millis)]
+        t: Duration,
+        m: Move,
+        s: Score,
+        #[strategy(0f64..=1f64)] e: f64,
+    ) {
+        let mut b = Budget::new(t..t * 2);
+        b.update(m, s, e);
+
+        for _ in 0..10 {
+            let soft = b.soft();
+            b.update(m, s, e);
+            assert!(b.soft() <= soft);
+        }
+    }
+
+    #[proptest]
+    fn soft_limit_grows_when_score_drops(
+        #[strategy(..1000u64)]
+        #[map(Duration::from_millis)]
+        t: Duration,
+        m: Move,
+        #[strategy(-100i16..=100)] s: i16,
+        #[strategy(1i16..=50)] d: i16,
+        #[strategy(0f64..=1f64)] e: f64,
+    ) {
+        let mut stable = Budget::new(t..t * 2);
+        stable.update(m, Score::new(s), e);
+        let mut dropping = stable.clone();
+
+        stable.update(m, Score::new(s), e);
+        dropping.update(m, Score::new(s - d), e);
+        assert!(dropping.soft() >= stable.soft());
+    }
+
+    #[proptest]
+    fn soft_limit_grows_when_best_move_takes_less_effort(
+        #[strategy(..1000u64)]
+        #[map(Duration::from_millis)]
+        t: Duration,
+        m: Move,
+        s: Score,
+        #[strategy(0f64..=1f64)] e: f64,
+        #[strategy(0f64..=#e)] f: f64,
+    ) {
+        let mut high = Budget::new(t..t * 2);
+        let mut low = high.clone();
+
+        high.update(m, s, e);
+        low.update(m, s, f);
+        assert!(low.soft() >= high.soft());
+    }
+}
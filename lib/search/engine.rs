@@ -1,24 +1,32 @@
-use crate::chess::{Move, Position};
+use crate::chess::{Move, Position, Zobrist};
 use crate::nnue::{Evaluator, Value};
 use crate::search::*;
 use crate::util::{Assume, Counter, Integer, Timer, Trigger};
 use arrayvec::ArrayVec;
-use derive_more::with_trait::{Constructor, Deref};
+use derive_more::with_trait::Deref;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
 use std::time::{Duration, Instant};
-use std::{num::Saturating, ops::Range, thread};
+use std::{mem, num::Saturating, ops::Range, thread};
 
 #[cfg(test)]
-use proptest::strategy::LazyJust;
+use proptest::{collection::vec, prelude::*, strategy::LazyJust};
 
 /// The search result.
-#[derive(Debug, Clone, Eq, PartialEq, Hash, Deref, Constructor)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Deref)]
 #[cfg_attr(test, derive(test_strategy::Arbitrary))]
 pub struct SearchResult<const N: usize = { Depth::MAX as _ }> {
     depth: Depth,
+    seldepth: Ply,
     time: Duration,
     nodes: u64,
+    #[cfg_attr(test, strategy(..=1000u16))]
+    hashfull: u16,
+    bound: ScoreBound,
     #[deref]
     pv: Pv<N>,
+    #[cfg_attr(test, strategy(vec(any::<Pv<N>>(), ..4)))]
+    variations: Vec<Pv<N>>,
 }
 
 impl<const N: usize> SearchResult<N> {
@@ -28,6 +36,12 @@ impl<const N: usize> SearchResult<N> {
         self.depth
     }
 
+    /// The maximum number of plies searched.
+    #[inline(always)]
+    pub fn seldepth(&self) -> Ply {
+        self.seldepth
+    }
+
     /// The duration searched.
     #[inline(always)]
     pub fn time(&self) -> Duration {
@@ -45,6 +59,49 @@ impl<const N: usize> SearchResult<N> {
     pub fn nps(&self) -> f64 {
         self.nodes as f64 / self.time().as_secs_f64()
     }
+
+    /// How full the transposition table is in permille.
+    #[inline(always)]
+    pub fn hashfull(&self) -> u16 {
+        self.hashfull
+    }
+
+    /// The score of the principal variation.
+    ///
+    /// It's only a bound if the score fell outside of the aspiration window.
+    #[inline(always)]
+    pub fn bound(&self) -> ScoreBound {
+        self.bound
+    }
+
+    /// The principal variations ranked from best to worst.
+    #[inline(always)]
+    pub fn pvs(&self) -> impl Iterator<Item = &Pv<N>> {
+        [&self.pv].into_iter().chain(&self.variations)
+    }
+}
+
+/// The progress of the search.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Progress<'a, const N: usize = { Depth::MAX as _ }> {
+    /// A partial [`SearchResult`].
+    Partial(&'a SearchResult<N>),
+    /// The root [`Move`] currently being searched and its ordinal number.
+    CurrMove(Move, usize),
+}
+
+/// Completes the best line currently known with the lines of the last
+/// fully completed iteration that start with a different move.
+fn merge<const N: usize>(best: Option<Pv<N>>, pvs: Vec<Pv<N>>, multipv: usize) -> Vec<Pv<N>> {
+    let mut lines = Vec::from_iter(best);
+    for pv in pvs {
+        if lines.iter().all(|l| l.head() != pv.head()) {
+            lines.push(pv);
+        }
+    }
+
+    lines.truncate(multipv);
+    lines
 }
 
 /// A chess engine.
@@ -56,6 +113,10 @@ pub struct Search<'a> {
     value: [Value; Ply::MAX as usize + 1],
     killers: [Killers; Ply::MAX as usize + 1],
     continuation: [Option<&'a Reply>; Ply::MAX as usize + 1],
+    pondering: Option<(&'a Trigger, Duration)>,
+    nodes: u64,
+    effort: u64,
+    seldepth: Ply,
     timestamp: Instant,
 }
 
@@ -71,10 +132,24 @@ impl<'a> Search<'a> {
             value,
             killers,
             continuation,
+            pondering: None,
+            nodes: 0,
+            effort: 0,
+            seldepth: Ply::new(0),
             timestamp: Instant::now(),
         }
     }
 
+    /// Starts the timer with the duration reserved for the search once pondering stops.
+    fn ponder(&mut self) {
+        if let Some((ponder, duration)) = self.pondering {
+            if !ponder.is_armed() {
+                self.ctrl.timer().start(duration);
+                self.pondering = None;
+            }
+        }
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn record(
         &mut self,
@@ -114,10 +189,24 @@ impl<'a> Search<'a> {
         self.tt.set(pos.zobrist(), tpos);
     }
 
-    fn result<const N: usize>(&self, limits: &Limits, depth: Depth, pv: Pv<N>) -> SearchResult<N> {
-        let nodes = limits.nodes() - self.ctrl.counter().get();
-        let time = self.timestamp.elapsed();
-        SearchResult::new(depth, time, nodes, pv)
+    fn result<const N: usize>(
+        &self,
+        depth: Depth,
+        bound: fn(Score) -> ScoreBound,
+        mut pvs: Vec<Pv<N>>,
+    ) -> SearchResult<N> {
+        let pv = pvs.remove(0);
+
+        SearchResult {
+            depth,
+            seldepth: self.seldepth,
+            time: self.timestamp.elapsed(),
+            nodes: self.ctrl.counter().counted(),
+            hashfull: self.tt.hashfull(),
+            bound: bound(pv.score()),
+            pv,
+            variations: pvs,
+        }
     }
 
     /// An implementation of the [improving heuristic].
@@ -218,7 +307,10 @@ impl<'a> Search<'a> {
         mut depth: Depth,
         ply: Ply,
     ) -> Result<Pv<N>, Interrupted> {
+        self.ponder();
         self.ctrl.interrupted()?;
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply);
 
         (ply > 0).assume();
         let (alpha, beta) = match pos.outcome() {
@@ -231,7 +323,7 @@ impl<'a> Search<'a> {
             return Ok(Pv::empty(alpha));
         }
 
-        self.value[ply.cast::<usize>()] = pos.evaluate();
+        self.value[ply.cast::<usize>()] = pos.evaluate() + self.noise(pos.zobrist());
         let transposition = self.tt.get(pos.zobrist());
         let transposed = match transposition {
             None => Pv::empty(self.value[ply.cast::<usize>()].saturate()),
@@ -325,7 +417,7 @@ impl<'a> Search<'a> {
             }
         }
 
-        match self.pvs(pos, &moves, alpha..beta, depth, ply)? {
+        match self.pvs(pos, &moves, alpha..beta, depth, ply, |_, _| {})? {
             None => Ok(transposed.truncate()),
             Some(pv) => Ok(pv),
         }
@@ -333,6 +425,8 @@ impl<'a> Search<'a> {
 
     /// An implementation of [PVS].
     ///
+    /// Every move is announced to `currmove` along with its ordinal number before it's searched.
+    ///
     /// [PVS]: https://www.chessprogramming.org/Principal_Variation_Search
     fn pvs<const N: usize>(
         &mut self,
@@ -341,14 +435,17 @@ impl<'a> Search<'a> {
         bounds: Range<Score>,
         depth: Depth,
         ply: Ply,
+        mut currmove: impl FnMut(Move, usize),
     ) -> Result<Option<Pv<N>>, Interrupted> {
         let (alpha, beta) = (bounds.start, bounds.end);
         let is_pv = alpha + 1 < beta;
         let draft = depth - ply;
 
+        let mut nodes = self.nodes;
         let (mut head, mut tail) = match moves.last() {
             None => return Ok(None),
             Some(&(m, _)) => {
+                currmove(m, 1);
                 let mut next = pos.clone();
                 next.play(m);
                 self.tt.prefetch(next.zobrist());
@@ -358,6 +455,7 @@ impl<'a> Search<'a> {
             }
         };
 
+        let mut effort = self.nodes - nodes;
         let improving = self.improving(ply);
         for (idx, &(m, _)) in moves.iter().rev().skip(1).enumerate() {
             let alpha = match tail.score() {
@@ -369,6 +467,8 @@ impl<'a> Search<'a> {
                 break;
             }
 
+            currmove(m, idx + 2);
+            nodes = self.nodes;
             let mut next = pos.clone();
             next.play(m);
 
@@ -381,101 +481,188 @@ impl<'a> Search<'a> {
             };
 
             if partial > tail {
-                (head, tail) = (m, partial);
+                (head, tail, effort) = (m, partial, self.nodes - nodes);
             }
         }
 
+        if ply == 0 {
+            self.effort = effort;
+        }
+
         self.record(pos, moves, bounds, depth, ply, head, tail.score());
         Ok(Some(tail.transpose(head)))
     }
 
     /// An implementation of [aspiration windows] with [iterative deepening].
     ///
+    /// Every iteration searches up to `multipv` lines in turn,
+    /// each of which excludes the root moves of the lines ranked above it.
+    /// On the clock, the [`Budget`] decides whether there's time for another iteration.
+    ///
     /// [aspiration windows]: https://www.chessprogramming.org/Aspiration_Windows
     /// [iterative deepening]: https://www.chessprogramming.org/Iterative_Deepening
     fn aw<const N: usize>(
         &mut self,
         pos: &Evaluator,
         limits: &Limits,
+        searchmoves: &[Move],
         time: Range<Duration>,
+        mut progress: impl FnMut(Progress<'_, N>),
     ) -> SearchResult<N> {
         self.value[0] = pos.evaluate();
         let mut depth = Depth::new(0);
-        let mut moves: ArrayVec<_, 255> = pos.moves().flatten().map(|m| (m, pos.gain(m))).collect();
-        let mut pv = match moves.iter().max_by_key(|(_, rating)| *rating) {
-            None if !pos.is_check() => return self.result(limits, depth, Pv::empty(Score::new(0))),
-            None => return self.result(limits, depth, Pv::empty(Score::mated(Ply::new(0)))),
-            Some((m, _)) => match self.tt.get(pos.zobrist()) {
-                None => Pv::new(self.value[0].saturate(), Line::singular(*m)),
-                Some(t) => t.transpose(Ply::new(0)).truncate(),
+        let moves: ArrayVec<_, 255> = pos
+            .moves()
+            .flatten()
+            .filter(|m| searchmoves.is_empty() || searchmoves.contains(m))
+            .map(|m| (m, pos.gain(m)))
+            .collect();
+
+        let mut pvs = match moves.iter().max_by_key(|(_, rating)| *rating) {
+            None if !pos.is_check() => {
+                let pvs = vec![Pv::empty(Score::new(0))];
+                return self.result(depth, ScoreBound::Exact, pvs);
+            }
+
+            None => {
+                let pvs = vec![Pv::empty(Score::mated(Ply::new(0)))];
+                return self.result(depth, ScoreBound::Exact, pvs);
+            }
+
+            Some((m, _)) => match self.tt.get(pos.zobrist()).map(|t| t.transpose(Ply::new(0))) {
+                Some(t) if moves.iter().any(|&(n, _)| t.head() == Some(n)) => vec![t.truncate()],
+                _ => vec![Pv::new(self.value[0].saturate(), Line::singular(*m))],
             },
         };
 
         if matches!(limits, Limits::Clock(..)) && moves.len() < 2 {
-            return self.result(limits, depth, pv);
+            return self.result(depth, ScoreBound::Exact, pvs);
         }
 
+        let timestamp = self.timestamp;
+        let mut budget = Budget::new(time);
+        let multipv = self.multipv().min(moves.len());
         while depth < limits.depth() {
             depth += 1;
+            self.seldepth = Ply::new(0);
+
+            let mut effort = 1.;
+            let mut lines: Vec<Pv<N>> = Vec::with_capacity(multipv);
+            while lines.len() < multipv {
+                let mut pv = pvs
+                    .iter()
+                    .find(|pv| lines.iter().all(|l| l.head() != pv.head()))
+                    .cloned();
+
+                let mut draft = depth;
+                let mut delta = Saturating(5i16);
+                let (mut lower, mut upper) = match (depth.get(), &pv) {
+                    (..=4, _) | (_, None) => (Score::lower(), Score::upper()),
+                    (_, Some(pv)) => (pv.score() - delta, pv.score() + delta),
+                };
 
-            let mut draft = depth;
-            let mut delta = Saturating(5i16);
-            let (mut lower, mut upper) = match depth.get() {
-                ..=4 => (Score::lower(), Score::upper()),
-                _ => (pv.score() - delta, pv.score() + delta),
-            };
+                'aw: loop {
+                    self.ponder();
+                    if self.ctrl.timer().remaining() < Some(budget.hard() - budget.soft()) {
+                        let pvs = merge(lines.into_iter().next().or(pv), pvs, multipv);
+                        return self.result(depth - 1, ScoreBound::Exact, pvs);
+                    }
 
-            'aw: loop {
-                if self.ctrl.timer().remaining() < Some(time.end - time.start) {
-                    return self.result(limits, depth - 1, pv);
-                }
+                    let mut candidates: ArrayVec<_, 255> = moves
+                        .iter()
+                        .filter(|(m, _)| lines.iter().all(|l| l.head() != Some(*m)))
+                        .map(|&(m, _)| match pv.as_ref().and_then(|pv| pv.head()) {
+                            Some(head) if head == m => (m, Value::upper()),
+                            _ => (m, pos.gain(m) + self.history.get(pos, m)),
+                        })
+                        .collect();
+
+                    candidates.sort_unstable_by_key(|(_, rating)| *rating);
+                    let currmove = |m, n| {
+                        if timestamp.elapsed() >= Duration::from_secs(1) {
+                            progress(Progress::CurrMove(m, n));
+                        }
+                    };
+
+                    let nodes = self.nodes;
+                    let bounds = lower..upper;
+                    let partial =
+                        match self.pvs(pos, &candidates, bounds, draft, Ply::new(0), currmove) {
+                            Ok(partial) => partial.assume(),
+                            Err(_) => {
+                                let pvs = merge(lines.into_iter().next().or(pv), pvs, multipv);
+                                return self.result(depth - 1, ScoreBound::Exact, pvs);
+                            }
+                        };
+
+                    delta *= 2;
+                    match partial.score() {
+                        score if (-lower..Score::upper()).contains(&-score) => {
+                            draft = depth;
+                            upper = lower / 2 + upper / 2;
+                            lower = score - delta;
+                            if let (true, Some(pv)) = (lines.is_empty(), &pv) {
+                                let failed = Pv::new(score, pv.moves().clone());
+                                let result = self.result(depth, ScoreBound::Upper, vec![failed]);
+                                progress(Progress::Partial(&result));
+                            }
+                        }
+
+                        score if (upper..Score::upper()).contains(&score) => {
+                            draft = Depth::new(1).max(draft - 1);
+                            upper = score + delta;
+                            if lines.is_empty() {
+                                let pvs = vec![partial.clone()];
+                                let result = self.result(depth, ScoreBound::Lower, pvs);
+                                progress(Progress::Partial(&result));
+                            }
+
+                            pv = Some(partial);
+                        }
 
-                for (m, rating) in moves.iter_mut() {
-                    if Some(*m) == pv.head() {
-                        *rating = Value::upper();
-                    } else {
-                        *rating = pos.gain(*m) + self.history.get(pos, *m);
+                        _ => {
+                            if lines.is_empty() {
+                                effort = self.effort as f64 / (self.nodes - nodes).max(1) as f64;
+                            }
+
+                            lines.push(partial);
+                            break 'aw;
+                        }
                     }
                 }
+            }
 
-                moves.sort_unstable_by_key(|(_, rating)| *rating);
-                let partial = match self.pvs(pos, &moves, lower..upper, draft, Ply::new(0)) {
-                    Err(_) => return self.result(limits, depth - 1, pv),
-                    Ok(partial) => partial.assume(),
-                };
+            lines.sort_by(|a, b| b.cmp(a));
+            pvs = lines;
 
-                delta *= 2;
-                match partial.score() {
-                    score if (-lower..Score::upper()).contains(&-score) => {
-                        draft = depth;
-                        upper = lower / 2 + upper / 2;
-                        lower = score - delta;
-                    }
+            // Extra lines searched below full strength are not reported.
+            let reported = Vec::from_iter(pvs.iter().take(self.engine.multipv.get()).cloned());
+            let result = self.result(depth, ScoreBound::Exact, reported);
+            progress(Progress::Partial(&result));
 
-                    score if (upper..Score::upper()).contains(&score) => {
-                        draft = Depth::new(1).max(draft - 1);
-                        upper = score + delta;
-                        pv = partial;
-                    }
+            if let (Limits::Clock(..), Some(m)) = (limits, pvs[0].head()) {
+                budget.update(m, pvs[0].score(), effort);
+            }
 
-                    _ => {
-                        pv = partial;
-                        break 'aw;
-                    }
+            if let (Some(m), Some(p)) = (limits.mate(), pvs[0].score().mate()) {
+                if p > 0 && p < 2 * m as i16 {
+                    break;
                 }
             }
         }
 
-        self.result(limits, depth, pv)
+        self.result(depth, ScoreBound::Exact, pvs)
     }
 
     fn go<const N: usize>(
         mut self,
         pos: &Evaluator,
         limits: &Limits,
+        searchmoves: &[Move],
         time: Range<Duration>,
+        progress: impl FnMut(Progress<'_, N>),
     ) -> SearchResult<N> {
-        self.aw(pos, limits, time)
+        self.aw(pos, limits, searchmoves, time, progress)
     }
 }
 
@@ -484,12 +671,18 @@ impl<'a> Search<'a> {
 #[cfg_attr(test, derive(test_strategy::Arbitrary))]
 pub struct Engine {
     threads: ThreadCount,
+    multipv: MultiPv,
     #[cfg_attr(test, map(|s: HashSize| TranspositionTable::new(s)))]
     tt: TranspositionTable,
     #[cfg_attr(test, strategy(LazyJust::new(History::default)))]
     history: History,
     #[cfg_attr(test, strategy(LazyJust::new(Continuation::default)))]
     continuation: Continuation,
+    #[cfg_attr(test, strategy(Just(SkillLevel::upper())))]
+    skill: SkillLevel,
+    move_overhead: MoveOverhead,
+    min_thinking_time: MinThinkingTime,
+    seed: u64,
 }
 
 impl Default for Engine {
@@ -508,41 +701,174 @@ impl Engine {
     pub fn with_options(options: &Options) -> Self {
         Engine {
             threads: options.threads,
+            multipv: options.multipv,
             tt: TranspositionTable::new(options.hash),
             history: History::default(),
             continuation: Continuation::default(),
+            skill: options.strength(),
+            move_overhead: options.move_overhead,
+            min_thinking_time: options.min_thinking_time,
+            seed: Pcg64::from_os_rng().random(),
+        }
+    }
+
+    /// Reconfigures the engine with the given [`Options`] in place.
+    ///
+    /// The transposition table is only reallocated if its size changes,
+    /// while the history tables are preserved unless the strength changes,
+    /// since everything learned is tainted by the noise of the previous skill level.
+    pub fn configure(&mut self, options: &Options) {
+        self.threads = options.threads;
+        self.multipv = options.multipv;
+        self.move_overhead = options.move_overhead;
+        self.min_thinking_time = options.min_thinking_time;
+        self.tt.resize(options.hash);
+
+        if self.skill != options.strength() {
+            self.skill = options.strength();
+            self.clear();
+        }
+    }
+
+    /// Discards everything learned from previous searches.
+    pub fn clear(&mut self) {
+        self.tt.clear();
+        self.history = History::default();
+        self.continuation = Continuation::default();
+    }
+
+    /// The number of principal variations to search for.
+    ///
+    /// Below full strength, a few extra lines are searched so that one of them may be picked instead,
+    /// but only the number of lines requested is ever reported.
+    fn multipv(&self) -> usize {
+        match self.skill == SkillLevel::upper() {
+            true => self.multipv.get(),
+            false => self.multipv.get().max(4),
+        }
+    }
+
+    /// The maximum number of nodes to search at the current skill level.
+    fn nodes(&self) -> u64 {
+        match self.skill == SkillLevel::upper() {
+            true => u64::MAX,
+            false => 64 << self.skill.get(),
+        }
+    }
+
+    /// A perturbation of the evaluation that grows as the skill level drops.
+    ///
+    /// The perturbation is drawn from the engine's seed and the position evaluated,
+    /// so it is consistent within a game, but differs from one engine to another.
+    #[inline(always)]
+    fn noise(&self, key: Zobrist) -> i16 {
+        match (SkillLevel::MAX - self.skill.get()) as u64 * 10 {
+            0 => 0,
+            a => ((self.seed ^ key.get()) % (2 * a + 1)) as i16 - a as i16,
         }
     }
 
+    /// Below full strength, picks one of the principal variations at random,
+    /// favoring the ones with higher scores.
+    ///
+    /// The choice is drawn from the engine's seed and the position searched,
+    /// so an engine always picks the same line given the same position and result.
+    fn pick<const N: usize>(&self, pos: &Position, mut result: SearchResult<N>) -> SearchResult<N> {
+        if self.skill == SkillLevel::upper() || result.variations.is_empty() {
+            return result;
+        }
+
+        let mut rng = Pcg64::seed_from_u64(self.seed ^ pos.zobrist().get());
+        let temperature = (SkillLevel::MAX - self.skill.get()) as f64 * 10.;
+
+        let best = result.score();
+        let weights = Vec::from_iter(result.pvs().map(|pv| {
+            let loss = (best - pv.score()).get() as f64;
+            (-loss / temperature).exp()
+        }));
+
+        let mut x = rng.random::<f64>() * weights.iter().sum::<f64>();
+        let idx = weights.iter().position(|w| {
+            x -= w;
+            x < 0.
+        });
+
+        if let Some(idx @ 1..) = idx {
+            mem::swap(&mut result.pv, &mut result.variations[idx - 1]);
+        }
+
+        result
+    }
+
     fn time_to_search(&self, pos: &Position, limits: &Limits) -> Range<Duration> {
-        let Limits::Clock(clock, inc) = *limits else {
+        let Limits::Clock(clock, inc, mtg, opponent) = *limits else {
             return limits.time()..limits.time();
         };
 
+        // Whatever is lost to communication delays is not available for searching.
+        let clock = clock.saturating_sub(self.move_overhead.into());
+
+        // The number of moves to go is exact if known, but one more is kept in reserve for safety.
+        let moves_left = match mtg {
+            None => 256 / pos.fullmoves().get().min(64),
+            Some(m) => u32::from(m) + 1,
+        };
+
+        // Spend a little more time when ahead on the clock and a little less when behind.
+        let scale = match opponent {
+            None => 1.,
+            Some(o) => (clock.as_secs_f64() / o.as_secs_f64().max(1E-3)).clamp(0.8, 1.25),
+        };
+
         let time_left = clock.saturating_sub(inc);
-        let moves_left = 256 / pos.fullmoves().get().min(64);
         let time_per_move = inc.saturating_add(time_left / moves_left).min(clock / 2);
+        let time_per_move = time_per_move.mul_f64(scale);
+        let time_per_move = time_per_move
+            .max(self.min_thinking_time.into())
+            .min(clock / 2);
         time_per_move / 2..time_per_move
     }
 
     /// Searches for the [principal variation][`Pv`].
-    pub fn search(&self, pos: &Evaluator, limits: &Limits, stopper: &Trigger) -> SearchResult {
+    ///
+    /// Up to [`Options::multipv`] principal variations are ranked, each starting with a distinct move.
+    /// Unless `searchmoves` is empty, only the moves it contains are considered at the root.
+    ///
+    /// While `ponder` is armed the search is not bound by time,
+    /// the time limit only starts counting down once `ponder` is disarmed.
+    ///
+    /// The partial [`SearchResult`] is reported to `progress` after every iteration,
+    /// as well as whenever the score falls outside of the aspiration window.
+    /// After the first second, every root move is also reported as it starts being searched.
+    pub fn search(
+        &self,
+        pos: &Evaluator,
+        limits: &Limits,
+        searchmoves: &[Move],
+        ponder: &Trigger,
+        stopper: &Trigger,
+        progress: impl FnMut(Progress<'_>),
+    ) -> SearchResult {
         let time = self.time_to_search(pos, limits);
-        let nodes = Counter::new(limits.nodes());
-        let timer = Timer::new(time.end);
+        let nodes = Counter::new(limits.nodes().min(self.nodes()));
+        let timer = Timer::infinite();
         let ctrl = Control::Limited(&nodes, &timer, stopper);
-        let search = Search::new(self, ctrl);
+        let mut search = Search::new(self, ctrl);
+        search.pondering = Some((ponder, time.end));
+        search.ponder();
 
         thread::scope(|s| {
             for _ in 1..self.threads.get() {
                 let time = time.clone();
                 let search = search.clone();
-                s.spawn(|| search.go::<1>(pos, limits, time));
+                s.spawn(|| search.go::<1>(pos, limits, searchmoves, time, |_| {}));
             }
 
-            let pv = search.go(pos, limits, time);
+            let result = search.go(pos, limits, searchmoves, time, progress);
             stopper.disarm();
-            pv
+            let mut result = self.pick(pos, result);
+            result.variations.truncate(self.multipv.get() - 1);
+            result
         })
     }
 }
@@ -550,6 +876,7 @@ impl Engine {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::chess::Zobrist;
     use proptest::{prop_assume, sample::Selector};
     use test_strategy::proptest;
 
@@ -560,6 +887,136 @@ mod tests {
         assert!(e.tt.size() <= o.hash);
     }
 
+    #[proptest]
+    fn configure_preserves_transpositions_if_hash_is_unchanged(
+        #[by_ref]
+        #[filter(#e.tt.capacity() > 0)]
+        mut e: Engine,
+        o: Options,
+        t: Transposition,
+        k: Zobrist,
+    ) {
+        let o = Options {
+            hash: e.tt.size(),
+            skill: e.skill,
+            limit_strength: false,
+            ..o
+        };
+        e.tt.set(k, t);
+        e.configure(&o);
+        assert_eq!((e.threads, e.multipv), (o.threads, o.multipv));
+        assert_eq!(e.skill, o.strength());
+        assert_eq!(e.tt.get(k), Some(t));
+    }
+
+    #[proptest]
+    fn configure_discards_transpositions_if_strength_changes(
+        #[by_ref]
+        #[filter(#e.tt.capacity() > 0)]
+        mut e: Engine,
+        #[filter(#o.strength() != #e.skill)] o: Options,
+        t: Transposition,
+        k: Zobrist,
+    ) {
+        let o = Options {
+            hash: e.tt.size(),
+            ..o
+        };
+        e.tt.set(k, t);
+        e.configure(&o);
+        assert_eq!(e.skill, o.strength());
+        assert_eq!(e.tt.get(k), None);
+    }
+
+    #[proptest]
+    fn clear_discards_transpositions(#[by_ref] mut e: Engine, t: Transposition, k: Zobrist) {
+        e.tt.set(k, t);
+        e.clear();
+        assert_eq!(e.tt.get(k), None);
+    }
+
+    #[proptest]
+    fn time_to_search_discounts_move_overhead(
+        e: Engine,
+        pos: Evaluator,
+        c: Duration,
+        #[map(Duration::from_millis)]
+        #[strategy(..1000u64)]
+        i: Duration,
+        m: Option<u16>,
+        o: Option<Duration>,
+    ) {
+        let time = e.time_to_search(&pos, &Limits::Clock(c, i, m, o));
+        assert!(time.end <= c.saturating_sub(e.move_overhead.into()) / 2);
+    }
+
+    #[proptest]
+    fn time_to_search_respects_min_thinking_time_if_clock_allows(
+        e: Engine,
+        pos: Evaluator,
+        c: Duration,
+        #[map(Duration::from_millis)]
+        #[strategy(..1000u64)]
+        i: Duration,
+        m: Option<u16>,
+        o: Option<Duration>,
+    ) {
+        let available = c.saturating_sub(e.move_overhead.into()) / 2;
+        let time = e.time_to_search(&pos, &Limits::Clock(c, i, m, o));
+        assert!(time.end >= available.min(e.min_thinking_time.into()));
+    }
+
+    #[proptest]
+    fn time_to_search_never_exceeds_half_the_clock(
+        e: Engine,
+        pos: Evaluator,
+        c: Duration,
+        #[map(Duration::from_millis)]
+        #[strategy(..1000u64)]
+        i: Duration,
+        m: Option<u16>,
+        o: Option<Duration>,
+    ) {
+        let time = e.time_to_search(&pos, &Limits::Clock(c, i, m, o));
+        assert!(time.start <= time.end);
+        assert!(time.end <= c / 2);
+    }
+
+    #[proptest]
+    fn time_to_search_spends_more_with_fewer_moves_to_go(
+        e: Engine,
+        pos: Evaluator,
+        #[map(Duration::from_secs)]
+        #[strategy(60..3600u64)]
+        c: Duration,
+        #[strategy(2..40u16)] m: u16,
+    ) {
+        let fewer = e.time_to_search(&pos, &Limits::Clock(c, Duration::ZERO, Some(m - 1), None));
+        let more = e.time_to_search(&pos, &Limits::Clock(c, Duration::ZERO, Some(m), None));
+        assert!(fewer.end >= more.end);
+    }
+
+    #[proptest]
+    fn time_to_search_paces_a_repeating_time_control(
+        mut e: Engine,
+        #[map(Duration::from_secs)]
+        #[strategy(60..3600u64)]
+        c: Duration,
+    ) {
+        e.min_thinking_time = MinThinkingTime::lower();
+
+        let mut clock = c;
+        for m in (1..=40u16).rev() {
+            let fen = format!("4k3/8/8/8/8/8/8/4K3 w - - 0 {}", 81 - m);
+            let pos: Position = fen.parse().unwrap();
+            let time = e.time_to_search(&pos, &Limits::Clock(clock, Duration::ZERO, Some(m), None));
+            assert!(time.end <= clock / m.into());
+            clock -= time.end;
+        }
+
+        assert!(clock >= c / 41);
+    }
+
     #[proptest]
     fn nw_returns_transposition_if_beta_too_low(
         #[by_ref]
@@ -713,7 +1170,11 @@ mod tests {
     ) {
         let limits = Duration::ZERO.into();
         let trigger = Trigger::armed();
-        assert_ne!(e.search(&pos, &limits, &trigger).head(), None);
+        assert_ne!(
+            e.search(&pos, &limits, &[], &Trigger::disarmed(), &trigger, |_| {})
+                .head(),
+            None
+        );
     }
 
     #[proptest]
@@ -723,7 +1184,213 @@ mod tests {
     ) {
         let limits = Depth::lower().into();
         let trigger = Trigger::armed();
-        assert_ne!(e.search(&pos, &limits, &trigger).head(), None);
+        assert_ne!(
+            e.search(&pos, &limits, &[], &Trigger::disarmed(), &trigger, |_| {})
+                .head(),
+            None
+        );
+    }
+
+    #[proptest]
+    fn search_reports_progress_after_every_iteration(
+        e: Engine,
+        #[filter(#pos.outcome().is_none())] pos: Evaluator,
+        d: Depth,
+    ) {
+        let mut depths = Vec::new();
+        let limits = d.into();
+        let trigger = Trigger::armed();
+        e.search(&pos, &limits, &[], &Trigger::disarmed(), &trigger, |p| {
+            if let Progress::Partial(r) = p {
+                depths.push(r.depth())
+            }
+        });
+
+        for i in 1..=d.get() {
+            assert!(depths.contains(&Depth::new(i)));
+        }
+    }
+
+    #[proptest]
+    fn search_result_is_exact_and_tracks_selective_depth(
+        e: Engine,
+        #[filter(#pos.outcome().is_none())] pos: Evaluator,
+        #[filter(#d > 0)] d: Depth,
+    ) {
+        let limits = d.into();
+        let trigger = Trigger::armed();
+        let result = e.search(&pos, &limits, &[], &Trigger::disarmed(), &trigger, |_| {});
+        assert_eq!(result.bound(), ScoreBound::Exact(result.score()));
+        assert!(result.seldepth() > 0);
+        assert!(result.hashfull() <= 1000);
+    }
+
+    #[proptest]
+    fn search_tracks_selective_depth_of_each_iteration_anew(
+        e: Engine,
+        #[filter(#pos.outcome().is_none())] pos: Evaluator,
+        #[filter(#d > 0)] d: Depth,
+    ) {
+        let mut search = Search::new(&e, Control::Unlimited);
+        search.seldepth = Ply::upper();
+        let result = search.go::<1>(&pos, &d.into(), &[], Duration::MAX..Duration::MAX, |_| {});
+        assert!(result.seldepth() < Ply::upper());
+    }
+
+    #[proptest]
+    fn search_below_full_strength_is_limited_in_nodes(
+        mut e: Engine,
+        #[filter(#pos.outcome().is_none())] pos: Evaluator,
+        #[strategy(..SkillLevel::MAX)]
+        #[map(SkillLevel::new)]
+        s: SkillLevel,
+    ) {
+        e.skill = s;
+        let limits = Limits::None;
+        let trigger = Trigger::armed();
+        let result = e.search(&pos, &limits, &[], &Trigger::disarmed(), &trigger, |_| {});
+        assert!(result.nodes() <= e.nodes());
+        assert!(pos.moves().flatten().any(|m| result.head() == Some(m)));
+    }
+
+    #[proptest]
+    fn search_below_full_strength_reports_only_multipv_lines(
+        mut e: Engine,
+        #[filter(#pos.outcome().is_none())] pos: Evaluator,
+        #[strategy(..SkillLevel::MAX)]
+        #[map(SkillLevel::new)]
+        s: SkillLevel,
+        d: Depth,
+    ) {
+        e.skill = s;
+        let limits = d.into();
+        let trigger = Trigger::armed();
+        let result = e.search(&pos, &limits, &[], &Trigger::disarmed(), &trigger, |p| {
+            if let Progress::Partial(r) = p {
+                assert!(r.pvs().count() <= e.multipv.get());
+            }
+        });
+
+        assert!(result.pvs().count() <= e.multipv.get());
+    }
+
+    #[proptest]
+    fn noise_below_full_strength_depends_on_seed(
+        mut e: Engine,
+        #[strategy(..SkillLevel::MAX)]
+        #[map(SkillLevel::new)]
+        s: SkillLevel,
+        k: Zobrist,
+    ) {
+        e.skill = s;
+        let noise = Vec::from_iter((0..64).map(|seed| {
+            e.seed = seed;
+            e.noise(k)
+        }));
+        assert!(noise.iter().any(|&n| n != noise[0]));
+    }
+
+    #[proptest]
+    fn pick_below_full_strength_preserves_principal_variations(
+        mut e: Engine,
+        #[strategy(..SkillLevel::MAX)]
+        #[map(SkillLevel::new)]
+        s: SkillLevel,
+        pos: Position,
+        r: SearchResult<3>,
+    ) {
+        e.skill = s;
+        let pvs = Vec::from_iter(r.pvs().cloned());
+        let picked = Vec::from_iter(e.pick(&pos, r).pvs().cloned());
+        assert_eq!(picked.len(), pvs.len());
+        assert!(pvs.iter().all(|pv| picked.contains(pv)));
+    }
+
+    #[proptest]
+    fn pick_below_full_strength_is_reproducible(
+        mut e: Engine,
+        #[strategy(..SkillLevel::MAX)]
+        #[map(SkillLevel::new)]
+        s: SkillLevel,
+        pos: Position,
+        r: SearchResult<3>,
+    ) {
+        e.skill = s;
+        assert_eq!(e.pick(&pos, r.clone()), e.pick(&pos, r));
+    }
+
+    #[proptest]
+    fn pick_at_full_strength_is_an_identity(e: Engine, pos: Position, r: SearchResult<3>) {
+        assert_eq!(e.pick(&pos, r.clone()), r);
+    }
+
+    #[proptest]
+    fn search_ranks_distinct_moves_up_to_multipv(
+        e: Engine,
+        #[filter(#pos.outcome().is_none())] pos: Evaluator,
+        #[filter(#d > 0)] d: Depth,
+    ) {
+        let limits = d.into();
+        let trigger = Trigger::armed();
+        let result = e.search(&pos, &limits, &[], &Trigger::disarmed(), &trigger, |_| {});
+        let heads = Vec::from_iter(result.pvs().map(|pv| pv.head()));
+        let count = pos.moves().flatten().count();
+
+        assert_eq!(heads.len(), e.multipv.get().min(count));
+        assert!(result.pvs().is_sorted_by(|a, b| a >= b));
+        for (i, head) in heads.iter().enumerate() {
+            assert!(!heads[..i].contains(head));
+        }
+    }
+
+    #[proptest]
+    fn merge_completes_best_line_with_distinct_lines_of_last_iteration(
+        best: Pv<3>,
+        pvs: Vec<Pv<3>>,
+        #[strategy(1..=4usize)] multipv: usize,
+    ) {
+        let lines = merge(Some(best.clone()), pvs.clone(), multipv);
+        assert_eq!(lines.first(), Some(&best));
+        assert!(lines.len() <= multipv);
+        for (i, line) in lines.iter().enumerate().skip(1) {
+            assert!(pvs.contains(line));
+            assert!(lines[..i].iter().all(|l| l.head() != line.head()));
+        }
+    }
+
+    #[proptest]
+    fn search_only_considers_searchmoves_at_the_root(
+        e: Engine,
+        #[filter(#pos.outcome().is_none())] pos: Evaluator,
+        d: Depth,
+        #[map(|s: Selector| s.select(#pos.moves().flatten()))] m: Move,
+    ) {
+        let limits = d.into();
+        let trigger = Trigger::armed();
+        let result = e.search(&pos, &limits, &[m], &Trigger::disarmed(), &trigger, |_| {});
+        assert_eq!(Vec::from_iter(result.pvs().map(|pv| pv.head())), [Some(m)]);
+    }
+
+    #[proptest]
+    fn search_ignores_time_limits_while_pondering(
+        e: Engine,
+        #[filter(#pos.outcome().is_none() && #pos.moves().flatten().count() > 1)] pos: Evaluator,
+    ) {
+        let limits = Limits::Clock(Duration::ZERO, Duration::ZERO, None, None);
+        let ponder = Trigger::armed();
+        let trigger = Trigger::armed();
+        let result = e.search(&pos, &limits, &[], &ponder, &trigger, |_| {});
+        assert_eq!(result.depth(), Depth::upper());
+    }
+
+    #[proptest]
+    fn search_stops_once_mate_is_proven(e: Engine, #[strategy(3..=4u8)] m: u8) {
+        let pos: Evaluator = "k7/8/1K6/8/8/8/8/7R w - - 0 1".parse()?;
+        let limits = Limits::Mate(m);
+        let trigger = Trigger::armed();
+        let result = e.search(&pos, &limits, &[], &Trigger::disarmed(), &trigger, |_| {});
+        assert_eq!(result.score().mate(), Some(Ply::new(1)));
+        assert!(result.depth() < limits.depth());
     }
 
     #[proptest]
@@ -733,6 +1400,10 @@ mod tests {
     ) {
         let limits = Limits::None;
         let trigger = Trigger::armed();
-        assert_ne!(e.search(&pos, &limits, &trigger).head(), None);
+        assert_ne!(
+            e.search(&pos, &limits, &[], &Trigger::disarmed(), &trigger, |_| {})
+                .head(),
+            None
+        );
     }
 }
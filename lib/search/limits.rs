@@ -18,9 +18,15 @@ pub enum Limits {
     /// The maximum amount of time to spend searching.
     Time(Duration),
 
-    /// The time remaining on the clock.
+    /// The time remaining on the clock, the time increment,
+    /// the number of moves to the next time control if any,
+    /// and the time remaining on the opponent's clock if known.
     #[from(ignore)]
-    Clock(Duration, Duration),
+    Clock(Duration, Duration, Option<u16>, Option<Duration>),
+
+    /// The maximum number of moves to mate.
+    #[from(ignore)]
+    Mate(u8),
 }
 
 impl Limits {
@@ -29,6 +35,7 @@ impl Limits {
     pub fn depth(&self) -> Depth {
         match self {
             Limits::Depth(d) => *d,
+            Limits::Mate(m) => (2 * *m as i16 - 1).saturate(),
             _ => Depth::upper(),
         }
     }
@@ -47,7 +54,7 @@ impl Limits {
     pub fn time(&self) -> Duration {
         match self {
             Limits::Time(t) => *t,
-            Limits::Clock(t, _) => *t,
+            Limits::Clock(t, ..) => *t,
             _ => Duration::MAX,
         }
     }
@@ -56,7 +63,7 @@ impl Limits {
     #[inline(always)]
     pub fn clock(&self) -> Duration {
         match self {
-            Limits::Clock(t, _) => *t,
+            Limits::Clock(t, ..) => *t,
             _ => Duration::MAX,
         }
     }
@@ -65,10 +72,37 @@ impl Limits {
     #[inline(always)]
     pub fn increment(&self) -> Duration {
         match self {
-            Limits::Clock(_, i) => *i,
+            Limits::Clock(_, i, ..) => *i,
             _ => Duration::ZERO,
         }
     }
+
+    /// Number of moves to the next time control if any.
+    #[inline(always)]
+    pub fn moves_to_go(&self) -> Option<u16> {
+        match self {
+            Limits::Clock(_, _, m, _) => *m,
+            _ => None,
+        }
+    }
+
+    /// Time left on the opponent's clock if known.
+    #[inline(always)]
+    pub fn opponent(&self) -> Option<Duration> {
+        match self {
+            Limits::Clock(.., o) => *o,
+            _ => None,
+        }
+    }
+
+    /// Maximum number of moves to mate if any.
+    #[inline(always)]
+    pub fn mate(&self) -> Option<u8> {
+        match self {
+            Limits::Mate(m) => Some(*m),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -82,11 +116,22 @@ mod tests {
     }
 
     #[proptest]
-    fn depth_returns_max_by_default(n: u64, t: Duration, i: Duration) {
+    fn depth_returns_max_by_default(
+        n: u64,
+        t: Duration,
+        i: Duration,
+        m: Option<u16>,
+        o: Option<Duration>,
+    ) {
         assert_eq!(Limits::None.depth(), Depth::MAX);
         assert_eq!(Limits::Nodes(n).depth(), Depth::MAX);
         assert_eq!(Limits::Time(t).depth(), Depth::MAX);
-        assert_eq!(Limits::Clock(t, i).depth(), Depth::MAX);
+        assert_eq!(Limits::Clock(t, i, m, o).depth(), Depth::MAX);
+    }
+
+    #[proptest]
+    fn depth_is_enough_to_find_mate(#[strategy(1..=(Depth::MAX as u8).div_ceil(2))] m: u8) {
+        assert_eq!(Limits::Mate(m).depth(), 2 * m as i8 - 1);
     }
 
     #[proptest]
@@ -95,11 +140,17 @@ mod tests {
     }
 
     #[proptest]
-    fn nodes_returns_max_by_default(d: Depth, t: Duration, i: Duration) {
+    fn nodes_returns_max_by_default(
+        d: Depth,
+        t: Duration,
+        i: Duration,
+        m: Option<u16>,
+        o: Option<Duration>,
+    ) {
         assert_eq!(Limits::None.nodes(), u64::MAX);
         assert_eq!(Limits::Depth(d).nodes(), u64::MAX);
         assert_eq!(Limits::Time(t).nodes(), u64::MAX);
-        assert_eq!(Limits::Clock(t, i).nodes(), u64::MAX);
+        assert_eq!(Limits::Clock(t, i, m, o).nodes(), u64::MAX);
     }
 
     #[proptest]
@@ -108,16 +159,23 @@ mod tests {
     }
 
     #[proptest]
-    fn time_returns_max_or_clock_by_default(d: Depth, n: u64, t: Duration, i: Duration) {
+    fn time_returns_max_or_clock_by_default(
+        d: Depth,
+        n: u64,
+        t: Duration,
+        i: Duration,
+        m: Option<u16>,
+        o: Option<Duration>,
+    ) {
         assert_eq!(Limits::None.time(), Duration::MAX);
         assert_eq!(Limits::Depth(d).time(), Duration::MAX);
         assert_eq!(Limits::Nodes(n).time(), Duration::MAX);
-        assert_eq!(Limits::Clock(t, i).time(), t);
+        assert_eq!(Limits::Clock(t, i, m, o).time(), t);
     }
 
     #[proptest]
-    fn clock_returns_value_if_set(t: Duration, i: Duration) {
-        assert_eq!(Limits::Clock(t, i).clock(), t);
+    fn clock_returns_value_if_set(t: Duration, i: Duration, m: Option<u16>, o: Option<Duration>) {
+        assert_eq!(Limits::Clock(t, i, m, o).clock(), t);
     }
 
     #[proptest]
@@ -129,8 +187,13 @@ mod tests {
     }
 
     #[proptest]
-    fn increment_returns_value_if_set(t: Duration, i: Duration) {
-        assert_eq!(Limits::Clock(t, i).increment(), i);
+    fn increment_returns_value_if_set(
+        t: Duration,
+        i: Duration,
+        m: Option<u16>,
+        o: Option<Duration>,
+    ) {
+        assert_eq!(Limits::Clock(t, i, m, o).increment(), i);
     }
 
     #[proptest]
@@ -140,4 +203,51 @@ mod tests {
         assert_eq!(Limits::Nodes(n).increment(), Duration::ZERO);
         assert_eq!(Limits::Time(t).increment(), Duration::ZERO);
     }
+
+    #[proptest]
+    fn moves_to_go_returns_value_if_set(t: Duration, i: Duration, m: u16, o: Option<Duration>) {
+        assert_eq!(Limits::Clock(t, i, Some(m), o).moves_to_go(), Some(m));
+    }
+
+    #[proptest]
+    fn moves_to_go_returns_none_by_default(d: Depth, n: u64, t: Duration) {
+        assert_eq!(Limits::None.moves_to_go(), None);
+        assert_eq!(Limits::Depth(d).moves_to_go(), None);
+        assert_eq!(Limits::Nodes(n).moves_to_go(), None);
+        assert_eq!(Limits::Time(t).moves_to_go(), None);
+    }
+
+    #[proptest]
+    fn opponent_returns_value_if_set(t: Duration, i: Duration, m: Option<u16>, o: Duration) {
+        assert_eq!(Limits::Clock(t, i, m, Some(o)).opponent(), Some(o));
+    }
+
+    #[proptest]
+    fn opponent_returns_none_by_default(d: Depth, n: u64, t: Duration) {
+        assert_eq!(Limits::None.opponent(), None);
+        assert_eq!(Limits::Depth(d).opponent(), None);
+        assert_eq!(Limits::Nodes(n).opponent(), None);
+        assert_eq!(Limits::Time(t).opponent(), None);
+    }
+
+    #[proptest]
+    fn mate_returns_value_if_set(m: u8) {
+        assert_eq!(Limits::Mate(m).mate(), Some(m));
+    }
+
+    #[proptest]
+    fn mate_returns_none_by_default(
+        d: Depth,
+        n: u64,
+        t: Duration,
+        i: Duration,
+        m: Option<u16>,
+        o: Option<Duration>,
+    ) {
+        assert_eq!(Limits::None.mate(), None);
+        assert_eq!(Limits::Depth(d).mate(), None);
+        assert_eq!(Limits::Nodes(n).mate(), None);
+        assert_eq!(Limits::Time(t).mate(), None);
+        assert_eq!(Limits::Clock(t, i, m, o).mate(), None);
+    }
 }
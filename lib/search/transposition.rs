@@ -220,13 +220,36 @@ pub struct TranspositionTable {
 impl TranspositionTable {
     const WIDTH: usize = size_of::<<Option<SignedTransposition> as Binary>::Bits>();
 
+    #[inline(always)]
+    fn capacity_for(size: HashSize) -> usize {
+        (1 + size.get() / 2).next_power_of_two() / Self::WIDTH
+    }
+
     /// Constructs a transposition table of at most `size` many bytes.
     #[inline(always)]
     pub fn new(size: HashSize) -> Self {
-        let capacity = (1 + size.get() / 2).next_power_of_two() / Self::WIDTH;
-
         TranspositionTable {
-            cache: (0..capacity).map(|_| AtomicU64::default()).collect(),
+            cache: (0..Self::capacity_for(size))
+                .map(|_| AtomicU64::default())
+                .collect(),
+        }
+    }
+
+    /// Resizes this table to at most `size` many bytes.
+    ///
+    /// The table is only reallocated, and therefore cleared, if its capacity changes.
+    #[inline(always)]
+    pub fn resize(&mut self, size: HashSize) {
+        if Self::capacity_for(size) != self.capacity() {
+            *self = Self::new(size);
+        }
+    }
+
+    /// Discards all [`Transposition`]s.
+    #[inline(always)]
+    pub fn clear(&mut self) {
+        for slot in &mut self.cache {
+            *slot.get_mut() = 0;
         }
     }
 
@@ -242,6 +265,16 @@ impl TranspositionTable {
         self.cache.len()
     }
 
+    /// An estimate of how full this table is in permille.
+    ///
+    /// Only the first thousand slots are sampled.
+    #[inline(always)]
+    pub fn hashfull(&self) -> u16 {
+        let sample = &self.cache[..self.capacity().min(1000)];
+        let occupied = sample.iter().filter(|s| s.load(Relaxed) != 0).count();
+        (occupied * 1000 / sample.len().max(1)) as u16
+    }
+
     /// Instructs the CPU to load the slot associated with `key` onto the cache.
     #[inline(always)]
     pub fn prefetch(&self, key: Zobrist) {
@@ -384,6 +417,53 @@ mod tests {
         assert_eq!(tt.size(), tt.cache.len() * TranspositionTable::WIDTH);
     }
 
+    #[proptest]
+    fn resize_preserves_transpositions_if_capacity_is_unchanged(
+        #[by_ref]
+        #[filter(#tt.capacity() > 0)]
+        mut tt: TranspositionTable,
+        t: Transposition,
+        k: Zobrist,
+    ) {
+        tt.set(k, t);
+        tt.resize(tt.size());
+        assert_eq!(tt.get(k), Some(t));
+    }
+
+    #[proptest]
+    fn resize_changes_table_size(mut tt: TranspositionTable, s: HashSize) {
+        tt.resize(s);
+        assert_eq!(tt.size(), TranspositionTable::new(s).size());
+    }
+
+    #[proptest]
+    fn clear_discards_all_transpositions(mut tt: TranspositionTable, t: Transposition, k: Zobrist) {
+        tt.set(k, t);
+        tt.clear();
+        assert_eq!(tt.get(k), None);
+    }
+
+    #[proptest]
+    fn hashfull_is_zero_once_table_is_cleared(mut tt: TranspositionTable) {
+        tt.clear();
+        assert_eq!(tt.hashfull(), 0);
+    }
+
+    #[proptest]
+    fn hashfull_is_one_thousand_once_table_is_full(
+        #[by_ref]
+        #[filter(#tt.capacity() > 0)]
+        tt: TranspositionTable,
+        t: Transposition,
+    ) {
+        for slot in &tt.cache {
+            let st = Some(SignedTransposition(Default::default(), t.encode()));
+            slot.store(st.encode().get(), Relaxed);
+        }
+
+        assert_eq!(tt.hashfull(), 1000);
+    }
+
     #[proptest]
     fn get_does_nothing_if_capacity_is_zero(k: Zobrist) {
         assert_eq!(TranspositionTable::new(HashSize::new(0)).get(k), None);
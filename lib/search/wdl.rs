@@ -0,0 +1,134 @@
+use crate::chess::{Position, Role};
+use crate::{search::Score, util::Integer};
+
+/// A [win-draw-loss] model.
+///
+/// The probability of winning is a logistic function of the [`Score`],
+/// whose parameters depend on the amount of material left on the board.
+///
+/// The parameters were fitted to the outcomes of self-play games, see `examples/wdl.rs`.
+///
+/// [win-draw-loss]: https://www.chessprogramming.org/Pawn_Advantage,_Win_Percentage,_and_Elo
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct WdlModel {
+    a: f64,
+    b: f64,
+}
+
+impl WdlModel {
+    const A: [f64; 4] = [253.135, -387.875, 192.920, -13.002];
+    const B: [f64; 4] = [487.581, -803.236, 458.766, -65.619];
+
+    /// Constructs the model for a [`Position`].
+    pub fn new(pos: &Position) -> Self {
+        let material: u32 = pos
+            .iter()
+            .map(|(p, _)| match p.role() {
+                Role::Pawn => 1,
+                Role::Knight | Role::Bishop => 3,
+                Role::Rook => 5,
+                Role::Queen => 9,
+                Role::King => 0,
+            })
+            .sum();
+
+        let m = material.clamp(17, 78) as f64 / 58.;
+        let poly = |c: [f64; 4]| c.iter().fold(0., |acc, &c| acc * m + c);
+
+        WdlModel {
+            a: poly(Self::A),
+            b: poly(Self::B),
+        }
+    }
+
+    /// The [`Score`] in centipawns, such that +100 means a 50% chance of winning.
+    pub fn centipawns(&self, score: Score) -> i32 {
+        (score.get() as f64 * 100. / self.a).round() as i32
+    }
+
+    /// The win, draw and loss probabilities in permille.
+    pub fn wdl(&self, score: Score) -> (u16, u16, u16) {
+        let (win, loss) = match score.mate() {
+            Some(_) if score.get() > 0 => (1., 0.),
+            Some(_) => (0., 1.),
+            None => {
+                let s = score.get() as f64;
+                let win = 1. / (1. + ((self.a - s) / self.b).exp());
+                let loss = 1. / (1. + ((self.a + s) / self.b).exp());
+                (win, loss)
+            }
+        };
+
+        let w = (win * 1000.).round() as u16;
+        let l = ((loss * 1000.).round() as u16).min(1000 - w);
+        (w, 1000 - w - l, l)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::Perspective;
+    use crate::nnue::Value;
+    use std::cmp::Ordering;
+    use test_strategy::proptest;
+
+    #[proptest]
+    fn expected_score_grows_with_score(pos: Position, s: Score, #[filter(#t > #s)] t: Score) {
+        let model = WdlModel::new(&pos);
+        let (w, d, _) = model.wdl(s);
+        let (x, e, _) = model.wdl(t);
+        assert!(2 * x + e >= 2 * w + d);
+    }
+
+    #[proptest]
+    fn expected_score_is_even_at_zero(pos: Position) {
+        let (w, d, _) = WdlModel::new(&pos).wdl(Score::new(0));
+        assert_eq!(2 * w + d, 1000);
+    }
+
+    #[proptest]
+    fn wdl_adds_up_to_one_thousand(pos: Position, s: Score) {
+        let (w, d, l) = WdlModel::new(&pos).wdl(s);
+        assert_eq!(w + d + l, 1000);
+    }
+
+    #[proptest]
+    fn wdl_is_symmetric(pos: Position, s: Score) {
+        let (w, d, l) = WdlModel::new(&pos).wdl(s);
+        assert_eq!(WdlModel::new(&pos).wdl(s.flip()), (l, d, w));
+    }
+
+    #[proptest]
+    fn win_probability_grows_with_score(pos: Position, s: Score, #[filter(#t > #s)] t: Score) {
+        let model = WdlModel::new(&pos);
+        assert!(model.wdl(t).0 >= model.wdl(s).0);
+        assert!(model.wdl(t).2 <= model.wdl(s).2);
+    }
+
+    #[proptest]
+    fn mate_is_a_certain_outcome(pos: Position, #[strategy(0i16..=10)] p: i16) {
+        let model = WdlModel::new(&pos);
+        assert_eq!(model.wdl(Score::mating(p.saturate())), (1000, 0, 0));
+        assert_eq!(model.wdl(Score::mated(p.saturate())), (0, 0, 1000));
+    }
+
+    #[proptest]
+    fn one_hundred_centipawns_means_even_chance_of_winning(pos: Position, s: Score) {
+        let model = WdlModel::new(&pos);
+        match model.centipawns(s).cmp(&100) {
+            Ordering::Less => assert!(model.wdl(s).0 <= 500),
+            Ordering::Equal => {}
+            Ordering::Greater => assert!(model.wdl(s).0 >= 500),
+        }
+    }
+
+    #[proptest]
+    fn centipawns_preserves_sign(pos: Position, #[filter(#v != Value::new(0))] v: Value) {
+        let s = v.convert::<Score>().unwrap();
+        assert_eq!(
+            WdlModel::new(&pos).centipawns(s).signum(),
+            s.get().signum() as i32
+        );
+    }
+}
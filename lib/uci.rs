@@ -1,18 +1,30 @@
-use crate::chess::{Color, Move, Perspective, Square};
-use crate::nnue::Evaluator;
-use crate::search::{Depth, Engine, HashSize, Limits, Options, ThreadCount};
+use crate::chess::{Bitboard, Color, Move, ParsePositionError, Perspective, Position, Square};
+use crate::nnue::{self, Evaluator};
+use crate::search::{Depth, Engine, HashSize, Limits, MultiPv, Options, Progress, ScoreBound};
+use crate::search::{Elo, MinThinkingTime, MoveOverhead, SearchResult, SkillLevel};
+use crate::search::{ThreadCount, WdlModel};
 use crate::util::{Assume, Integer, Trigger};
 use derive_more::with_trait::{Display, Error, From};
-use futures::channel::oneshot::channel as oneshot;
+use futures::channel::{mpsc::unbounded, oneshot::channel as oneshot};
 use futures::{future::FusedFuture, prelude::*, select_biased as select, stream::FusedStream};
 use nom::error::{Error as ParseError, ErrorKind};
-use nom::{branch::*, bytes::complete::*, combinator::*, sequence::*, *};
+use nom::{
+    branch::*,
+    bytes::complete::*,
+    character::complete::{multispace1, one_of},
+    combinator::*,
+};
+use nom::{multi::*, sequence::*, *};
+use std::collections::VecDeque;
+use std::fmt::{self, Debug, Formatter};
 use std::str::{self, FromStr};
-use std::{fmt::Debug, io::Write, mem::transmute, thread, time::Instant};
+use std::time::{Duration, Instant};
+use std::{io::Write, mem::transmute, thread};
 
 #[cfg(test)]
 use proptest::{prelude::*, strategy::LazyJust};
 
+mod bench;
 mod parser;
 
 pub use parser::*;
@@ -39,24 +51,116 @@ where
     }
 }
 
+/// A [`Move`] in UCI notation, where castling is encoded as king takes rook in Chess960.
 #[derive(Copy, Clone, Eq, PartialEq, Hash)]
-struct UciMove(Move);
+struct UciMove(Move, bool);
+
+impl Display for UciMove {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            UciMove(m, true) => write!(f, "{m:#}"),
+            UciMove(m, false) => write!(f, "{m}"),
+        }
+    }
+}
 
 impl PartialEq<str> for UciMove {
     fn eq(&self, other: &str) -> bool {
         let mut buffer = [b'\0'; 5];
-        write!(&mut buffer[..], "{}", self.0).assume();
+        write!(&mut buffer[..], "{self}").assume();
         let len = if buffer[4] == b'\0' { 4 } else { 5 };
         other == unsafe { str::from_utf8_unchecked(&buffer[..len]) }
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct UciInfo<'a> {
+    result: &'a SearchResult,
+    multipv: usize,
+    model: WdlModel,
+    show_wdl: bool,
+    chess960: bool,
+}
+
+impl<'a> UciInfo<'a> {
+    fn report(
+        result: &'a SearchResult,
+        model: WdlModel,
+        show_wdl: bool,
+        chess960: bool,
+    ) -> impl Iterator<Item = Self> {
+        (0..result.pvs().count()).map(move |multipv| UciInfo {
+            result,
+            multipv,
+            model,
+            show_wdl,
+            chess960,
+        })
+    }
+}
+
+impl Display for UciInfo<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let pv = self.result.pvs().nth(self.multipv).assume();
+        let multipv = self.multipv + 1;
+        let line = pv.moves();
+        let depth = self.result.depth();
+        let seldepth = self.result.seldepth();
+        let time = self.result.time().as_millis();
+        let nodes = self.result.nodes();
+        let nps = self.result.nps() as u64;
+        let hashfull = self.result.hashfull();
+
+        let score = match pv.score().mate() {
+            None => format!("cp {}", self.model.centipawns(pv.score())),
+            Some(p) => format!("mate {}", (p + p.get().signum()) / 2),
+        };
+
+        write!(
+            f,
+            "info depth {depth} seldepth {seldepth} multipv {multipv} time {time}"
+        )?;
+        write!(
+            f,
+            " nodes {nodes} nps {nps} hashfull {hashfull} score {score}"
+        )?;
+
+        match self.result.bound() {
+            ScoreBound::Lower(_) if self.multipv == 0 => write!(f, " lowerbound")?,
+            ScoreBound::Upper(_) if self.multipv == 0 => write!(f, " upperbound")?,
+            _ => {}
+        }
+
+        if self.show_wdl {
+            let (w, d, l) = self.model.wdl(pv.score());
+            write!(f, " wdl {w} {d} {l}")?;
+        }
+
+        match self.chess960 {
+            true => write!(f, " pv {line:#}"),
+            false => write!(f, " pv {line}"),
+        }
+    }
+}
+
 /// The reason why executing the UCI command failed.
 #[derive(Debug, Display, Clone, Eq, PartialEq, Error, From)]
 pub enum UciError<I, E> {
-    #[display("failed to parse the uci command")]
+    #[display("failed to parse `{}`", _0.input)]
     #[from(forward)]
     ParseError(ParseError<I>),
+    #[display("invalid fen `{_0}`, {_1}")]
+    #[from(ignore)]
+    InvalidFen(#[error(not(source))] I, ParsePositionError),
+    #[display("illegal move `{_0}`")]
+    #[from(ignore)]
+    IllegalMove(#[error(not(source))] I),
+    #[display("unknown option `{_0}`")]
+    #[from(ignore)]
+    UnknownOption(#[error(not(source))] I),
+    #[display("invalid value `{value}` for option `{name}`, {reason}")]
+    #[from(ignore)]
+    InvalidValue { name: I, value: I, reason: String },
     #[display("fatal error")]
     #[from(ignore)]
     Fatal(E),
@@ -74,8 +178,15 @@ pub struct Uci<I, O> {
     output: O,
     #[cfg_attr(test, strategy(LazyJust::new(move || Engine::with_options(&#options))))]
     engine: Engine,
+    #[cfg_attr(test, map(|o: Options| Options { skill: SkillLevel::upper(), limit_strength: false, ..o }))]
     options: Options,
     position: Evaluator,
+    #[cfg_attr(test, strategy(Just(false)))]
+    chess960: bool,
+    #[cfg_attr(test, strategy(Just(false)))]
+    show_wdl: bool,
+    #[cfg_attr(test, strategy(LazyJust::new(VecDeque::new)))]
+    pending: VecDeque<String>,
 }
 
 impl<I, O> Uci<I, O> {
@@ -87,59 +198,188 @@ impl<I, O> Uci<I, O> {
             engine: Engine::default(),
             options: Options::default(),
             position: Evaluator::default(),
+            chess960: false,
+            show_wdl: false,
+            pending: VecDeque::new(),
         }
     }
 }
 
 impl<I: FusedStream<Item = String> + Unpin, O: Sink<String> + Unpin> Uci<I, O> {
-    async fn go(&mut self, limits: &Limits) -> Result<(), O::Error> {
+    async fn go(
+        &mut self,
+        limits: &Limits,
+        searchmoves: &[Move],
+        ponder: bool,
+        mut infinite: bool,
+    ) -> Result<(), O::Error> {
         let stopper = Trigger::armed();
+        let pondering = if ponder {
+            Trigger::armed()
+        } else {
+            Trigger::disarmed()
+        };
+
+        let chess960 = self.chess960;
+        let show_wdl = self.show_wdl;
+        let model = WdlModel::new(&self.position);
+
+        let (tx, mut rx) = unbounded();
 
-        let mut search =
-            unsafe { unblock(|| self.engine.search(&self.position, limits, &stopper)) };
+        let mut search = unsafe {
+            unblock(|| {
+                let mut last = None;
+                let progress = |p: Progress<'_>| match p {
+                    Progress::Partial(r) => {
+                        for info in UciInfo::report(r, model, show_wdl, chess960) {
+                            tx.unbounded_send(info.to_string()).assume();
+                        }
 
-        let result = loop {
+                        last = Some(r.clone());
+                    }
+
+                    Progress::CurrMove(m, n) => {
+                        let m = UciMove(m, chess960);
+                        let info = format!("info currmove {m} currmovenumber {n}");
+                        tx.unbounded_send(info).assume();
+                    }
+                };
+
+                let (pos, ponder) = (&self.position, &pondering);
+                let result =
+                    self.engine
+                        .search(pos, limits, searchmoves, ponder, &stopper, progress);
+
+                (result, last)
+            })
+        };
+
+        let (result, last) = loop {
             select! {
+                info = rx.next() => {
+                    if let Some(info) = info {
+                        self.output.send(info).await?;
+                    }
+                },
+
                 result = search => break result,
+
                 line = self.input.next() => {
                     match line.as_deref().map(str::trim_ascii) {
-                        None => break search.await,
-                        Some("stop") => { stopper.disarm(); },
-                        Some(cmd) => eprintln!("ignored unsupported command `{cmd}` during search"),
+                        None => {
+                            pondering.disarm();
+                            infinite = false;
+                            break search.await;
+                        }
+                        Some("stop") => {
+                            pondering.disarm();
+                            stopper.disarm();
+                            infinite = false;
+                        }
+                        Some("ponderhit") => { pondering.disarm(); },
+                        Some("isready") => self.output.send("readyok".to_string()).await?,
+                        Some("quit") => {
+                            pondering.disarm();
+                            stopper.disarm();
+                            infinite = false;
+                            self.pending.push_back("quit".to_string());
+                            break search.await;
+                        }
+                        Some("") => {}
+                        Some(cmd) => self.pending.push_back(cmd.to_string()),
                     }
                 }
             }
         };
 
-        let line = result.moves();
-        let depth = result.depth();
-        let time = result.time().as_millis();
-        let nodes = result.nodes();
-        let nps = result.nps() as u64;
+        while let Ok(info) = rx.try_recv() {
+            self.output.send(info).await?;
+        }
 
-        let score = match result.score().mate() {
-            None => format!("cp {}", result.score()),
-            Some(p) => format!("mate {}", (p + p.get().signum()) / 2),
-        };
+        // The last iteration has already been reported unless the search was interrupted.
+        let reported = last.is_some_and(|r| {
+            (r.depth(), r.bound()) == (result.depth(), result.bound()) && r.pvs().eq(result.pvs())
+        });
 
-        let info = format!(
-            "info depth {depth} time {time} nodes {nodes} nps {nps} score {score} pv {line}"
-        );
+        if !reported {
+            for info in UciInfo::report(&result, model, show_wdl, chess960) {
+                self.output.send(info.to_string()).await?;
+            }
+        }
 
-        self.output.send(info).await?;
+        // The best move is only reported once the GUI stops an infinite search.
+        while pondering.is_armed() || infinite {
+            match self.input.next().await.as_deref().map(str::trim_ascii) {
+                None | Some("stop") => {
+                    pondering.disarm();
+                    infinite = false;
+                }
+                Some("ponderhit") => {
+                    pondering.disarm();
+                }
+                Some("isready") => self.output.send("readyok".to_string()).await?,
+                Some("quit") => {
+                    pondering.disarm();
+                    infinite = false;
+                    self.pending.push_back("quit".to_string());
+                }
+                Some("") => {}
+                Some(cmd) => self.pending.push_back(cmd.to_string()),
+            }
+        }
 
-        if let Some(m) = result.head() {
-            self.output.send(format!("bestmove {m}")).await?;
+        let mut moves = result.moves().iter().map(|m| UciMove(m, chess960));
+        match (moves.next(), moves.next()) {
+            (None, _) => {}
+            (Some(m), None) => self.output.send(format!("bestmove {m}")).await?,
+            (Some(m), Some(p)) => self.output.send(format!("bestmove {m} ponder {p}")).await?,
         }
 
         Ok(())
     }
 
+    async fn bench(&mut self, depth: Depth, options: &Options) -> Result<(), O::Error> {
+        let mut engine = Engine::with_options(options);
+        let (limits, mut nodes, mut time) = (Limits::Depth(depth), 0, Duration::ZERO);
+
+        for fen in bench::POSITIONS {
+            engine.clear();
+            let pos = Evaluator::from_str(fen).assume();
+            let (ponder, stopper) = (Trigger::disarmed(), Trigger::armed());
+            let timer = Instant::now();
+            let result = engine.search(&pos, &limits, &[], &ponder, &stopper, |_| {});
+            time += timer.elapsed();
+            nodes += result.nodes();
+        }
+
+        let millis = time.as_millis();
+        let info = format!("{nodes} nodes {} nps", nodes as u128 * 1000 / millis.max(1));
+        self.output.send(info).await
+    }
+
     async fn perft(&mut self, depth: Depth) -> Result<(), O::Error> {
         let timer = Instant::now();
-        let nodes = self.position.perft(depth);
+        let pos: &Position = &self.position;
+        let divide: Vec<_> = match depth.get() {
+            0 => Vec::new(),
+            _ => Vec::from_iter(pos.moves().flatten().map(|m| {
+                let mut next = pos.clone();
+                next.play(m);
+                (UciMove(m, self.chess960), next.perft(depth - 1))
+            })),
+        };
+
+        let nodes = match depth.get() {
+            0 => 1,
+            _ => divide.iter().map(|&(_, n)| n).sum(),
+        };
+
         let millis = timer.elapsed().as_millis();
 
+        for (m, n) in divide {
+            self.output.send(format!("{m}: {n}")).await?;
+        }
+
         let info = format!(
             "info time {millis} nodes {nodes} nps {}",
             nodes as u128 * 1000 / millis.max(1)
@@ -148,35 +388,51 @@ impl<I: FusedStream<Item = String> + Unpin, O: Sink<String> + Unpin> Uci<I, O> {
         self.output.send(info).await
     }
 
-    async fn execute<'i>(&mut self, input: &'i str) -> Result<(), UciError<&'i str, O::Error>> {
+    /// Executes a single UCI command.
+    pub async fn execute<'i>(&mut self, input: &'i str) -> Result<(), UciError<&'i str, O::Error>> {
         let mut cmd = t(alt((
             tag("position"),
+            preceded((tag("go"), multispace1), tag("perft")),
             tag("go"),
             tag("perft"),
+            tag("bench"),
             tag("eval"),
             tag("setoption"),
             tag("isready"),
             tag("ucinewgame"),
             tag("uci"),
+            terminated(tag("d"), eof),
         )));
 
         match cmd.parse(input).finish()? {
             (args, "position") => {
-                let word6 = (word, t(word), t(word), t(word), t(word), word);
-                let fen = field("fen", t(recognize(word6))).map_res(Evaluator::from_str);
-                let startpos = t(tag("startpos")).map(|_| Evaluator::default());
+                let fen = field("fen", t(alt((take_until("moves"), rest)))).map(Some);
+                let startpos = t(tag("startpos")).map(|_| None);
                 let moves = opt(field("moves", rest));
 
                 let mut position = terminated((alt((startpos, fen)), moves), eof);
-                let (_, (mut pos, moves)) = position.parse(args).finish()?;
+                let (_, (fen, moves)) = position.parse(args).finish()?;
+
+                let mut pos = match fen.map(str::trim_ascii) {
+                    None => Evaluator::default(),
+                    Some(fen) => match Evaluator::from_str(fen) {
+                        Err(e) => return Err(UciError::InvalidFen(fen, e)),
+                        Ok(pos) => pos,
+                    },
+                };
 
                 if let Some(moves) = moves {
                     for s in moves.split_ascii_whitespace() {
                         let take2 = take::<_, _, ParseError<&str>>(2usize);
-                        let (_, whence) = take2.map_res(Square::from_str).parse(s).finish()?;
+                        let Ok((_, whence)) = take2.map_res(Square::from_str).parse(s).finish()
+                        else {
+                            return Err(UciError::IllegalMove(s));
+                        };
+
                         let moves = pos.moves().filter(|ms| ms.whence() == whence);
-                        let Some(m) = moves.flatten().find(|m| UciMove(*m) == *s) else {
-                            return Err(UciError::ParseError(ParseError::new(s, ErrorKind::Fail)));
+                        let Some(m) = moves.flatten().find(|m| UciMove(*m, self.chess960) == *s)
+                        else {
+                            return Err(UciError::IllegalMove(s));
                         };
 
                         pos.play(m);
@@ -199,27 +455,53 @@ impl<I: FusedStream<Item = String> + Unpin, O: Sink<String> + Unpin> Uci<I, O> {
                 let mate = field("mate", int);
                 let mtg = field("movestogo", int);
                 let inf = t(tag("infinite"));
+                let ponder = t(tag("ponder"));
+
+                let file = || one_of("abcdefgh");
+                let rank = || one_of("12345678");
+                let lan = recognize((file(), rank(), file(), rank(), opt(one_of("nbrq"))));
+                let sm = field("searchmoves", many1(t(lan)));
+
+                let params = (
+                    wtime, winc, btime, binc, time, nodes, depth, mate, mtg, inf, sm, ponder,
+                );
 
-                let params = (wtime, winc, btime, binc, time, nodes, depth, mate, mtg, inf);
-                let limits = gather(params).map(|(wt, wi, bt, bi, t, n, d, _, _, _)| {
-                    if let (Color::White, Some(clock)) = (turn, wt) {
-                        Limits::Clock(clock, wi.unwrap_or_default())
+                let go = gather(params).map(|(wt, wi, bt, bi, t, n, d, m, mtg, i, sm, p)| {
+                    let mtg = mtg.map(|m| m.saturate());
+                    let limits = if let (Color::White, Some(clock)) = (turn, wt) {
+                        Limits::Clock(clock, wi.unwrap_or_default(), mtg, bt)
                     } else if let (Color::Black, Some(clock)) = (turn, bt) {
-                        Limits::Clock(clock, bi.unwrap_or_default())
+                        Limits::Clock(clock, bi.unwrap_or_default(), mtg, wt)
                     } else if let Some(movetime) = t {
                         Limits::Time(movetime)
                     } else if let Some(nodes) = n {
                         Limits::Nodes(nodes.saturate())
                     } else if let Some(depth) = d {
                         Limits::Depth(depth.saturate())
+                    } else if let Some(mate) = m {
+                        Limits::Mate(mate.saturate())
                     } else {
                         Limits::None
-                    }
+                    };
+
+                    (limits, sm.unwrap_or_default(), p.is_some(), i.is_some())
                 });
 
-                let mut go = terminated(opt(limits), eof).map(|l| l.unwrap_or_default());
-                let (_, limits) = go.parse(args).finish()?;
-                self.go(&limits).await.map_err(UciError::Fatal)?;
+                let mut go = terminated(opt(go), eof).map(|g| g.unwrap_or_default());
+                let (_, (limits, sm, ponder, infinite)) = go.parse(args).finish()?;
+
+                let mut searchmoves = Vec::with_capacity(sm.len());
+                for s in sm {
+                    let mut moves = self.position.moves().flatten();
+                    let Some(m) = moves.find(|m| UciMove(*m, self.chess960) == *s) else {
+                        return Err(UciError::IllegalMove(s));
+                    };
+
+                    searchmoves.push(m);
+                }
+
+                let go = self.go(&limits, &searchmoves, ponder, infinite);
+                go.await.map_err(UciError::Fatal)?;
             }
 
             (args, "perft") => {
@@ -229,6 +511,23 @@ impl<I: FusedStream<Item = String> + Unpin, O: Sink<String> + Unpin> Uci<I, O> {
                 self.perft(depth).await.map_err(UciError::Fatal)?;
             }
 
+            (args, "bench") => {
+                let depth = t(int).map(|i| i.saturate());
+                let threads = t(word).map_res(|s| s.parse());
+                let hash = t(word).map_res(|s| s.parse());
+                let mut bench = terminated((opt(depth), opt(threads), opt(hash)), eof);
+                let (_, (depth, threads, hash)) = bench.parse(args).finish()?;
+
+                let options = Options {
+                    threads: threads.unwrap_or_default(),
+                    hash: hash.unwrap_or_default(),
+                    ..Options::default()
+                };
+
+                let depth = depth.unwrap_or_else(|| 12.saturate());
+                self.bench(depth, &options).await.map_err(UciError::Fatal)?;
+            }
+
             ("", "eval") => {
                 let pos = &self.position;
                 let turn = self.position.turn();
@@ -237,26 +536,73 @@ impl<I: FusedStream<Item = String> + Unpin, O: Sink<String> + Unpin> Uci<I, O> {
                 self.output.send(info).await.map_err(UciError::Fatal)?;
             }
 
-            (args, "setoption") => {
-                let option = |n| preceded((t(tag("name")), tag_no_case(n), t(tag("value"))), word);
-
-                let options = gather2((
-                    option("hash").map_res(|s| s.parse()),
-                    option("threads").map_res(|s| s.parse()),
-                ));
+            ("", "d") => {
+                let pos = &self.position;
+                let squares =
+                    |bb: Bitboard| bb.iter().map(|sq| format!(" {sq}")).collect::<String>();
+                let moves = pos
+                    .moves()
+                    .flatten()
+                    .map(|m| format!(" {}", UciMove(m, self.chess960)));
+
+                let mut lines: Vec<_> = pos.board().ascii().lines().map(String::from).collect();
+                lines.push(format!("Fen: {pos}"));
+                lines.push(format!("Key: {:016X}", pos.zobrist().get()));
+                lines.push(format!("Checkers:{}", squares(pos.checkers())));
+                lines.push(format!("Pinned:{}", squares(pos.pinned())));
+                lines.push(format!("Repetitions: {}", pos.repetitions()));
+                lines.push(format!("Legal moves:{}", moves.collect::<String>()));
+
+                for line in lines {
+                    self.output.send(line).await.map_err(UciError::Fatal)?;
+                }
+            }
 
-                let mut setoption = terminated(options, eof);
-                let (_, (hash, threads)) = setoption.parse(args).finish()?;
+            (args, "setoption") => {
+                let name = preceded(t(tag("name")), alt((take_until(" value"), rest)));
+                let value = opt(field("value", rest));
+
+                let mut setoption = terminated((name, value), eof);
+                let (_, (name, value)) = setoption.parse(args).finish()?;
+                let name = name.trim_ascii();
+
+                match name.to_ascii_lowercase().as_str() {
+                    "hash" => self.options.hash = Self::value(name, value)?,
+                    "threads" => self.options.threads = Self::value(name, value)?,
+                    "multipv" => self.options.multipv = Self::value(name, value)?,
+                    "skill level" => self.options.skill = Self::value(name, value)?,
+                    "uci_limitstrength" => self.options.limit_strength = Self::value(name, value)?,
+                    "uci_elo" => self.options.elo = Self::value(name, value)?,
+                    "move overhead" => self.options.move_overhead = Self::value(name, value)?,
+                    "minimum thinking time" => {
+                        self.options.min_thinking_time = Self::value(name, value)?
+                    }
 
-                if let Some(h) = hash {
-                    self.options.hash = h;
-                }
+                    // Pondering is controlled by `go ponder`, so the `Ponder` option is only validated.
+                    "ponder" => Self::value::<bool>(name, value).map(drop)?,
+                    "uci_chess960" => self.chess960 = Self::value(name, value)?,
+                    "uci_showwdl" => self.show_wdl = Self::value(name, value)?,
+                    "clear hash" => self.engine.clear(),
+                    "evalfile" => {
+                        let path = value.unwrap_or_default().trim_ascii();
+                        match path {
+                            "" | "<empty>" => nnue::restore(),
+                            _ => nnue::load(path).map_err(|e| UciError::InvalidValue {
+                                name,
+                                value: path,
+                                reason: e.to_string(),
+                            })?,
+                        }
+
+                        // Scores learned with the previous network are meaningless to the new one.
+                        self.engine.clear();
+                        self.position = Evaluator::new(Position::clone(&self.position));
+                    }
 
-                if let Some(t) = threads {
-                    self.options.threads = t;
+                    _ => return Err(UciError::UnknownOption(name)),
                 }
 
-                self.engine = Engine::with_options(&self.options);
+                self.engine.configure(&self.options);
             }
 
             ("", "isready") => {
@@ -265,7 +611,7 @@ impl<I: FusedStream<Item = String> + Unpin, O: Sink<String> + Unpin> Uci<I, O> {
             }
 
             ("", "ucinewgame") => {
-                self.engine = Engine::with_options(&self.options);
+                self.engine.clear();
                 self.position = Evaluator::default();
             }
 
@@ -288,31 +634,107 @@ impl<I: FusedStream<Item = String> + Unpin, O: Sink<String> + Unpin> Uci<I, O> {
                     ThreadCount::upper()
                 );
 
+                let multipv = format!(
+                    "option name MultiPV type spin default {} min {} max {}",
+                    MultiPv::default(),
+                    MultiPv::lower(),
+                    MultiPv::upper()
+                );
+
+                let skill = format!(
+                    "option name Skill Level type spin default {} min {} max {}",
+                    SkillLevel::default(),
+                    SkillLevel::lower(),
+                    SkillLevel::upper()
+                );
+
+                let limit = "option name UCI_LimitStrength type check default false".to_string();
+
+                let elo = format!(
+                    "option name UCI_Elo type spin default {} min {} max {}",
+                    Elo::default(),
+                    Elo::lower(),
+                    Elo::upper()
+                );
+
+                let overhead = format!(
+                    "option name Move Overhead type spin default {} min {} max {}",
+                    MoveOverhead::default(),
+                    MoveOverhead::lower(),
+                    MoveOverhead::upper()
+                );
+
+                let min = format!(
+                    "option name Minimum Thinking Time type spin default {} min {} max {}",
+                    MinThinkingTime::default(),
+                    MinThinkingTime::lower(),
+                    MinThinkingTime::upper()
+                );
+
+                let ponder = "option name Ponder type check default false".to_string();
+                let chess960 = "option name UCI_Chess960 type check default false".to_string();
+                let wdl = "option name UCI_ShowWDL type check default false".to_string();
+                let clear = "option name Clear Hash type button".to_string();
+                let eval = "option name EvalFile type string default <empty>".to_string();
+
                 self.output.send(name).await.map_err(UciError::Fatal)?;
                 self.output.send(author).await.map_err(UciError::Fatal)?;
                 self.output.send(hash).await.map_err(UciError::Fatal)?;
                 self.output.send(threads).await.map_err(UciError::Fatal)?;
+                self.output.send(multipv).await.map_err(UciError::Fatal)?;
+                self.output.send(skill).await.map_err(UciError::Fatal)?;
+                self.output.send(limit).await.map_err(UciError::Fatal)?;
+                self.output.send(elo).await.map_err(UciError::Fatal)?;
+                self.output.send(overhead).await.map_err(UciError::Fatal)?;
+                self.output.send(min).await.map_err(UciError::Fatal)?;
+                self.output.send(ponder).await.map_err(UciError::Fatal)?;
+                self.output.send(chess960).await.map_err(UciError::Fatal)?;
+                self.output.send(wdl).await.map_err(UciError::Fatal)?;
+                self.output.send(clear).await.map_err(UciError::Fatal)?;
+                self.output.send(eval).await.map_err(UciError::Fatal)?;
                 self.output.send(uciok).await.map_err(UciError::Fatal)?;
             }
 
-            _ => unreachable!(),
+            (args, _) => return Err(ParseError::new(args, ErrorKind::Eof).into()),
         }
 
         Ok(())
     }
 
+    /// Parses the value of the option `name`.
+    fn value<'i, T>(name: &'i str, value: Option<&'i str>) -> Result<T, UciError<&'i str, O::Error>>
+    where
+        T: FromStr<Err: Display>,
+    {
+        let value = value.unwrap_or_default().trim_ascii();
+        value.parse().map_err(|e: T::Err| UciError::InvalidValue {
+            name,
+            value,
+            reason: e.to_string(),
+        })
+    }
+
     /// Runs the UCI server.
+    ///
+    /// Commands received while searching, other than those that control the search itself,
+    /// are only executed once the search is over.
     pub async fn run(&mut self) -> Result<(), O::Error> {
-        while let Some(line) = self.input.next().await {
+        loop {
+            let line = match self.pending.pop_front() {
+                Some(line) => line,
+                None => match self.input.next().await {
+                    Some(line) => line,
+                    None => break,
+                },
+            };
+
             match line.trim_ascii() {
                 "quit" => break,
                 "stop" | "" => continue,
                 cmd => match self.execute(cmd).await {
                     Ok(_) => continue,
                     Err(UciError::Fatal(e)) => return Err(e),
-                    Err(UciError::ParseError(_)) => {
-                        eprintln!("warning: ignored unrecognized command `{cmd}`")
-                    }
+                    Err(e) => self.output.send(format!("info string {e}")).await?,
                 },
             }
         }
@@ -326,11 +748,11 @@ mod tests {
     use super::*;
     use crate::{chess::Position, search::Depth};
     use futures::executor::block_on;
-    use nom::{character::complete::line_ending, multi::separated_list1};
+    use nom::character::complete::line_ending;
     use proptest::sample::Selector;
     use rand::seq::SliceRandom;
     use std::task::{Context, Poll};
-    use std::{collections::VecDeque, pin::Pin};
+    use std::{collections::HashSet, pin::Pin};
     use test_strategy::proptest;
 
     #[derive(Debug, Default, Clone, Eq, PartialEq)]
@@ -360,12 +782,25 @@ mod tests {
 
     fn info(input: &str) -> IResult<&str, &str, ParseError<&str>> {
         let depth = field("depth", int);
+        let seldepth = field("seldepth", int);
+        let multipv = field("multipv", int);
         let time = field("time", int);
         let nodes = field("nodes", int);
         let nps = field("nps", int);
+        let hashfull = field("hashfull", int);
         let score = field("score", (t(alt([tag("cp"), tag("mate")])), int));
+        let bound = opt(t(alt([tag("lowerbound"), tag("upperbound")])));
+        let wdl = opt(field("wdl", (int, t(int), int)));
         let pv = field("pv", separated_list1(tag(" "), word));
-        recognize((tag("info"), depth, time, nodes, nps, score, pv)).parse(input)
+        let fields = (depth, seldepth, multipv, time, nodes, nps, hashfull);
+        let currmove = (field("currmove", word), field("currmovenumber", int));
+        let pv = recognize((fields, score, bound, wdl, pv));
+        recognize((tag("info"), alt((pv, recognize(currmove))))).parse(input)
+    }
+
+    fn bestmove(input: &str) -> IResult<&str, &str, ParseError<&str>> {
+        let ponder = opt(field("ponder", word));
+        recognize((field("bestmove", word), ponder)).parse(input)
     }
 
     #[proptest]
@@ -413,6 +848,20 @@ mod tests {
         assert!(uci.output.is_empty());
     }
 
+    #[proptest]
+    fn handles_position_with_abbreviated_fen(
+        #[by_ref] mut uci: MockUci,
+        pos: Evaluator,
+        #[strategy(4..=5usize)] n: usize,
+    ) {
+        let fen = Vec::from_iter(pos.to_string().split_ascii_whitespace().take(n)).join(" ");
+        uci.input = StaticStream::new([format!("position fen {fen} moves")]);
+        assert_eq!(block_on(uci.run()), Ok(()));
+        assert_eq!(uci.position.board().ascii(), pos.board().ascii());
+        assert_eq!(uci.position.fullmoves().get(), 1);
+        assert!(uci.output.is_empty());
+    }
+
     #[proptest]
     fn handles_position_with_fen_and_moves(
         #[by_ref]
@@ -440,6 +889,35 @@ mod tests {
         assert!(uci.output.is_empty());
     }
 
+    #[proptest]
+    fn handles_position_with_castling(
+        #[any(StaticStream::new([
+            "position startpos moves e2e4 e7e5 g1f3 b8c6 f1c4 g8f6 e1g1",
+        ]))]
+        mut uci: MockUci,
+    ) {
+        let fen = "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQ1RK1 b kq - 5 4";
+        assert_eq!(block_on(uci.run()), Ok(()));
+        assert_eq!(uci.position.to_string(), fen);
+        assert!(uci.output.is_empty());
+    }
+
+    #[proptest]
+    fn handles_position_with_castling_as_king_takes_rook_in_chess960(
+        #[any(StaticStream::new([
+            "setoption name UCI_Chess960 value true",
+            "position fen rk5r/8/8/8/8/8/8/RK5R w HAha - 0 1 moves b1a1 b8a8",
+        ]))]
+        mut uci: MockUci,
+    ) {
+        assert_eq!(block_on(uci.run()), Ok(()));
+        assert_eq!(
+            uci.position.to_string(),
+            "2kr3r/8/8/8/8/8/8/2KR3R w - - 2 2"
+        );
+        assert!(uci.output.is_empty());
+    }
+
     #[proptest]
     fn ignores_position_with_invalid_fen(
         #[any(StaticStream::new([format!("position fen {}", #_s)]))] mut uci: MockUci,
@@ -448,7 +926,8 @@ mod tests {
         let pos = uci.position.clone();
         assert_eq!(block_on(uci.run()), Ok(()));
         assert_eq!(uci.position, pos);
-        assert!(uci.output.is_empty());
+        assert_eq!(uci.output.len(), 1);
+        assert!(uci.output[0].starts_with("info string "));
     }
 
     #[proptest]
@@ -459,19 +938,20 @@ mod tests {
         let pos = uci.position.clone();
         assert_eq!(block_on(uci.run()), Ok(()));
         assert_eq!(uci.position, pos);
-        assert!(uci.output.is_empty());
+        assert_eq!(uci.output.len(), 1);
+        assert!(uci.output[0].starts_with("info string illegal move "));
     }
 
     #[proptest]
     fn handles_position_with_illegal_move(
-        #[filter(!Position::default().moves().flatten().any(|m| UciMove(m) == *#_m.to_string()))]
-        _m: Move,
-        #[any(StaticStream::new([format!("position startpos moves {}", #_m)]))] mut uci: MockUci,
+        #[filter(!Position::default().moves().flatten().any(|n| UciMove(n, false) == *#m.to_string()))]
+        m: Move,
+        #[any(StaticStream::new([format!("position startpos moves {}", #m)]))] mut uci: MockUci,
     ) {
         let pos = uci.position.clone();
         assert_eq!(block_on(uci.run()), Ok(()));
         assert_eq!(uci.position, pos);
-        assert!(uci.output.is_empty());
+        assert_eq!(uci.output, [format!("info string illegal move `{m}`")]);
     }
 
     #[proptest]
@@ -483,6 +963,7 @@ mod tests {
         #[strategy(..10u8)] wi: u8,
         #[strategy(..10u8)] bt: u8,
         #[strategy(..10u8)] bi: u8,
+        #[strategy(..10u8)] mtg: u8,
         idx: usize,
     ) {
         let mut input = [
@@ -491,6 +972,7 @@ mod tests {
             format!("btime {}", bt),
             format!("winc {}", wi),
             format!("binc {}", bi),
+            format!("movestogo {}", mtg),
         ];
 
         input[1..].shuffle(&mut rand::rng());
@@ -499,9 +981,47 @@ mod tests {
 
         let output = uci.output.join("\n");
 
-        let bestmove = field("bestmove", word);
-        let mut pattern = recognize(terminated((info, line_ending, bestmove), eof));
+        let mut pattern = recognize(terminated((many1((info, line_ending)), bestmove), eof));
+        assert_eq!(pattern.parse(&*output).finish(), Ok(("", &*output)));
+    }
+
+    #[proptest]
+    fn handles_go_with_wdl(
+        #[filter(#uci.position.outcome().is_none())]
+        #[any(StaticStream::new([format!("go depth {}", #_d)]))]
+        mut uci: MockUci,
+        _d: Depth,
+    ) {
+        uci.show_wdl = true;
+        assert_eq!(block_on(uci.run()), Ok(()));
+
+        let output = uci.output.join("\n");
+
+        let mut pattern = recognize(terminated((many1((info, line_ending)), bestmove), eof));
         assert_eq!(pattern.parse(&*output).finish(), Ok(("", &*output)));
+        assert!(
+            uci.output
+                .iter()
+                .filter(|l| l.starts_with("info"))
+                .all(|l| l.contains(" wdl "))
+        );
+    }
+
+    #[proptest]
+    fn reports_score_bound_on_the_principal_line_only(
+        #[filter(#r.pvs().count() > 1)] r: SearchResult,
+        pos: Evaluator,
+    ) {
+        let model = WdlModel::new(&pos);
+        let mut lines = UciInfo::report(&r, model, false, false).map(|i| i.to_string());
+        let bound = match r.bound() {
+            ScoreBound::Lower(_) => " lowerbound ",
+            ScoreBound::Upper(_) => " upperbound ",
+            ScoreBound::Exact(_) => " pv ",
+        };
+
+        assert!(lines.next().unwrap().contains(bound));
+        assert!(lines.all(|l| !l.contains("bound")));
     }
 
     #[proptest]
@@ -515,11 +1035,27 @@ mod tests {
 
         let output = uci.output.join("\n");
 
-        let bestmove = field("bestmove", word);
-        let mut pattern = recognize(terminated((info, line_ending, bestmove), eof));
+        let mut pattern = recognize(terminated((many1((info, line_ending)), bestmove), eof));
         assert_eq!(pattern.parse(&*output).finish(), Ok(("", &*output)));
     }
 
+    #[proptest]
+    fn reports_every_iteration_once(
+        #[by_ref]
+        #[filter(#uci.position.outcome().is_none())]
+        mut uci: MockUci,
+        #[strategy(1..=4i8)]
+        #[map(Depth::new)]
+        d: Depth,
+    ) {
+        uci.input = StaticStream::new([format!("go depth {d}")]);
+        assert_eq!(block_on(uci.run()), Ok(()));
+
+        let infos = Vec::from_iter(uci.output.iter().filter(|l| l.starts_with("info depth")));
+        let unique = HashSet::<_>::from_iter(infos.iter().map(|l| l.split(" time ").next()));
+        assert_eq!(unique.len(), infos.len());
+    }
+
     #[proptest]
     fn handles_go_nodes(
         #[filter(#uci.position.outcome().is_none())]
@@ -531,8 +1067,7 @@ mod tests {
 
         let output = uci.output.join("\n");
 
-        let bestmove = field("bestmove", word);
-        let mut pattern = recognize(terminated((info, line_ending, bestmove), eof));
+        let mut pattern = recognize(terminated((many1((info, line_ending)), bestmove), eof));
         assert_eq!(pattern.parse(&*output).finish(), Ok(("", &*output)));
     }
 
@@ -547,8 +1082,7 @@ mod tests {
 
         let output = uci.output.join("\n");
 
-        let bestmove = field("bestmove", word);
-        let mut pattern = recognize(terminated((info, line_ending, bestmove), eof));
+        let mut pattern = recognize(terminated((many1((info, line_ending)), bestmove), eof));
         assert_eq!(pattern.parse(&*output).finish(), Ok(("", &*output)));
     }
 
@@ -563,8 +1097,7 @@ mod tests {
 
         let output = uci.output.join("\n");
 
-        let bestmove = field("bestmove", word);
-        let mut pattern = recognize(terminated((info, line_ending, bestmove), eof));
+        let mut pattern = recognize(terminated((many1((info, line_ending)), bestmove), eof));
         assert_eq!(pattern.parse(&*output).finish(), Ok(("", &*output)));
     }
 
@@ -580,8 +1113,7 @@ mod tests {
 
         let output = uci.output.join("\n");
 
-        let bestmove = field("bestmove", word);
-        let mut pattern = recognize(terminated((info, line_ending, bestmove), eof));
+        let mut pattern = recognize(terminated((many1((info, line_ending)), bestmove), eof));
         assert_eq!(pattern.parse(&*output).finish(), Ok(("", &*output)));
     }
 
@@ -597,8 +1129,104 @@ mod tests {
 
         let output = uci.output.join("\n");
 
-        let bestmove = field("bestmove", word);
-        let mut pattern = recognize(terminated((info, line_ending, bestmove), eof));
+        let mut pattern = recognize(terminated((many1((info, line_ending)), bestmove), eof));
+        assert_eq!(pattern.parse(&*output).finish(), Ok(("", &*output)));
+    }
+
+    #[proptest]
+    fn handles_go_with_mate_once_proven(
+        #[any(StaticStream::new(["position fen k7/8/1K6/8/8/8/8/7R w - - 0 1", "go mate 2"]))]
+        mut uci: MockUci,
+    ) {
+        assert_eq!(block_on(uci.run()), Ok(()));
+
+        let output = uci.output.join("\n");
+        assert!(output.contains("score mate 1"));
+        assert!(output.ends_with("bestmove h1h8"));
+    }
+
+    #[proptest]
+    fn handles_go_with_searchmoves(
+        #[by_ref]
+        #[filter(#uci.position.outcome().is_none())]
+        mut uci: MockUci,
+        d: Depth,
+        selector: Selector,
+    ) {
+        let m = selector.select(uci.position.moves().flatten()).to_string();
+        uci.input = StaticStream::new([format!("go depth {d} searchmoves {m}")]);
+        assert_eq!(block_on(uci.run()), Ok(()));
+
+        let output = uci.output.join("\n");
+
+        let bestmove = (field("bestmove", tag(&*m)), opt(field("ponder", word)));
+        let mut pattern = recognize(terminated((many1((info, line_ending)), bestmove), eof));
+        assert_eq!(pattern.parse(&*output).finish(), Ok(("", &*output)));
+    }
+
+    #[proptest]
+    fn handles_go_with_castling_as_king_takes_rook_in_chess960(
+        #[any(StaticStream::new([
+            "setoption name UCI_Chess960 value true",
+            "position fen rk5r/8/8/8/8/8/8/RK5R w HAha - 0 1",
+            "go depth 2 searchmoves b1a1",
+        ]))]
+        mut uci: MockUci,
+    ) {
+        assert_eq!(block_on(uci.run()), Ok(()));
+
+        let output = uci.output.join("\n");
+
+        let bestmove = (field("bestmove", tag("b1a1")), opt(field("ponder", word)));
+        let mut pattern = recognize(terminated((many1((info, line_ending)), bestmove), eof));
+        assert_eq!(pattern.parse(&*output).finish(), Ok(("", &*output)));
+    }
+
+    #[proptest]
+    fn ignores_go_with_illegal_searchmoves(
+        #[by_ref]
+        #[filter(#uci.position.outcome().is_none())]
+        mut uci: MockUci,
+        m: Move,
+    ) {
+        prop_assume!(
+            uci.position
+                .moves()
+                .flatten()
+                .all(|n| n.to_string() != m.to_string())
+        );
+        uci.input = StaticStream::new([format!("go searchmoves {m}")]);
+        assert_eq!(block_on(uci.run()), Ok(()));
+        assert_eq!(uci.output, [format!("info string illegal move `{m}`")]);
+    }
+
+    #[proptest]
+    fn handles_go_ponder_until_ponderhit(
+        #[by_ref]
+        #[filter(#uci.position.outcome().is_none())]
+        #[any(StaticStream::new(["go ponder wtime 0 btime 0", "ponderhit"]))]
+        mut uci: MockUci,
+    ) {
+        assert_eq!(block_on(uci.run()), Ok(()));
+
+        let output = uci.output.join("\n");
+
+        let mut pattern = recognize(terminated((many1((info, line_ending)), bestmove), eof));
+        assert_eq!(pattern.parse(&*output).finish(), Ok(("", &*output)));
+    }
+
+    #[proptest]
+    fn handles_go_ponder_until_stop(
+        #[by_ref]
+        #[filter(#uci.position.outcome().is_none())]
+        #[any(StaticStream::new(["go ponder", "stop"]))]
+        mut uci: MockUci,
+    ) {
+        assert_eq!(block_on(uci.run()), Ok(()));
+
+        let output = uci.output.join("\n");
+
+        let mut pattern = recognize(terminated((many1((info, line_ending)), bestmove), eof));
         assert_eq!(pattern.parse(&*output).finish(), Ok(("", &*output)));
     }
 
@@ -613,8 +1241,53 @@ mod tests {
 
         let output = uci.output.join("\n");
 
-        let bestmove = field("bestmove", word);
-        let mut pattern = recognize(terminated((info, line_ending, bestmove), eof));
+        let mut pattern = recognize(terminated((many1((info, line_ending)), bestmove), eof));
+        assert_eq!(pattern.parse(&*output).finish(), Ok(("", &*output)));
+    }
+
+    #[proptest]
+    fn handles_isready_during_search(
+        #[by_ref]
+        #[filter(#uci.position.outcome().is_none())]
+        #[any(StaticStream::new(["go infinite", "isready", "stop"]))]
+        mut uci: MockUci,
+    ) {
+        assert_eq!(block_on(uci.run()), Ok(()));
+        assert!(uci.output.contains(&"readyok".to_string()));
+
+        let output = uci.output.last().unwrap();
+        assert_eq!(bestmove.parse(&**output).finish().map(|(i, _)| i), Ok(""));
+    }
+
+    #[proptest]
+    fn handles_quit_during_search(
+        #[by_ref]
+        #[filter(#uci.position.outcome().is_none())]
+        #[any(StaticStream::new(["go infinite", "quit", "isready"]))]
+        mut uci: MockUci,
+    ) {
+        assert_eq!(block_on(uci.run()), Ok(()));
+        assert!(!uci.output.contains(&"readyok".to_string()));
+
+        let output = uci.output.join("\n");
+
+        let mut pattern = recognize(terminated((many1((info, line_ending)), bestmove), eof));
+        assert_eq!(pattern.parse(&*output).finish(), Ok(("", &*output)));
+    }
+
+    #[proptest]
+    fn defers_commands_received_during_search(
+        #[by_ref]
+        #[filter(#uci.position.outcome().is_none())]
+        #[any(StaticStream::new(["go infinite", "position startpos", "stop"]))]
+        mut uci: MockUci,
+    ) {
+        assert_eq!(block_on(uci.run()), Ok(()));
+        assert_eq!(uci.position, Evaluator::default());
+
+        let output = uci.output.join("\n");
+
+        let mut pattern = recognize(terminated((many1((info, line_ending)), bestmove), eof));
         assert_eq!(pattern.parse(&*output).finish(), Ok(("", &*output)));
     }
 
@@ -630,6 +1303,75 @@ mod tests {
         assert!(uci.output.is_empty());
     }
 
+    #[proptest]
+    fn handles_perft(
+        #[by_ref]
+        #[filter(#uci.position.outcome().is_none())]
+        #[any(StaticStream::new(["perft 2"]))]
+        mut uci: MockUci,
+    ) {
+        let pos = uci.position.clone();
+        assert_eq!(block_on(uci.run()), Ok(()));
+
+        let (total, divide) = uci.output.split_last().unwrap();
+        assert_eq!(divide.len(), pos.moves().flatten().count());
+
+        let nodes: usize = divide
+            .iter()
+            .map(|line| line.split_once(": ").unwrap().1.parse::<usize>().unwrap())
+            .sum();
+
+        assert_eq!(nodes, pos.perft(2.saturate()));
+        assert!(total.contains(&format!(" nodes {nodes} ")));
+    }
+
+    #[proptest]
+    fn handles_go_perft(
+        #[any(StaticStream::new(["position startpos", "go perft 1"]))] mut uci: MockUci,
+    ) {
+        assert_eq!(block_on(uci.run()), Ok(()));
+        assert_eq!(uci.output.len(), 21);
+        assert!(uci.output.contains(&"e2e4: 1".to_string()));
+        assert!(uci.output[20].contains(" nodes 20 "));
+    }
+
+    #[proptest]
+    fn handles_perft_to_depth_zero(#[any(StaticStream::new(["perft 0"]))] mut uci: MockUci) {
+        assert_eq!(block_on(uci.run()), Ok(()));
+        assert_eq!(uci.output.len(), 1);
+        assert!(uci.output[0].contains(" nodes 1 "));
+    }
+
+    #[proptest]
+    fn handles_bench(#[any(StaticStream::new(["bench 1 1 0"]))] mut uci: MockUci) {
+        assert_eq!(block_on(uci.run()), Ok(()));
+
+        let output = uci.output.join("\n");
+
+        let mut pattern = recognize((int, tag(" nodes "), int, tag(" nps"), eof));
+        assert_eq!(pattern.parse(&*output).finish(), Ok(("", &*output)));
+    }
+
+    #[proptest]
+    fn bench_node_count_is_deterministic(
+        #[any(StaticStream::new(["bench 2"]))] mut a: MockUci,
+        #[any(StaticStream::new(["bench 2"]))] mut b: MockUci,
+    ) {
+        assert_eq!(block_on(a.run()), Ok(()));
+        assert_eq!(block_on(b.run()), Ok(()));
+
+        let nodes = |uci: &MockUci| uci.output[0].split_once(' ').map(|(n, _)| n.to_string());
+        assert_eq!(nodes(&a), nodes(&b));
+    }
+
+    #[proptest]
+    fn ignores_bench_with_invalid_arguments(
+        #[any(StaticStream::new(["bench 1 x"]))] mut uci: MockUci,
+    ) {
+        assert_eq!(block_on(uci.run()), Ok(()));
+        assert_eq!(uci.output, ["info string failed to parse `x`"]);
+    }
+
     #[proptest]
     fn handles_eval(#[any(StaticStream::new(["eval"]))] mut uci: MockUci) {
         let pos = uci.position.clone();
@@ -648,6 +1390,42 @@ mod tests {
         assert_eq!(pattern.parse(&*output).finish(), Ok(("", &*output)));
     }
 
+    #[proptest]
+    fn handles_d(#[any(StaticStream::new(["d"]))] mut uci: MockUci) {
+        let pos = uci.position.clone();
+        assert_eq!(block_on(uci.run()), Ok(()));
+
+        let ascii = pos.board().ascii();
+        assert_eq!(uci.output[..18].join("\n"), ascii);
+        assert!(uci.output.contains(&format!("Fen: {pos}")));
+        assert!(
+            uci.output
+                .contains(&format!("Key: {:016X}", pos.zobrist().get()))
+        );
+        assert!(
+            uci.output
+                .contains(&format!("Repetitions: {}", pos.repetitions()))
+        );
+
+        let moves = uci
+            .output
+            .last()
+            .unwrap()
+            .strip_prefix("Legal moves:")
+            .unwrap();
+        assert_eq!(
+            moves.split_whitespace().count(),
+            pos.moves().flatten().count()
+        );
+    }
+
+    #[proptest]
+    fn ignores_d_with_arguments(#[any(StaticStream::new(["d 1"]))] mut uci: MockUci) {
+        assert_eq!(block_on(uci.run()), Ok(()));
+        assert_eq!(uci.output.len(), 1);
+        assert!(uci.output[0].starts_with("info string failed to parse "));
+    }
+
     #[proptest]
     fn handles_uci(#[any(StaticStream::new(["uci"]))] mut uci: MockUci) {
         assert_eq!(block_on(uci.run()), Ok(()));
@@ -685,7 +1463,8 @@ mod tests {
         let o = uci.options.clone();
         assert_eq!(block_on(uci.run()), Ok(()));
         assert_eq!(uci.options, o);
-        assert!(uci.output.is_empty());
+        assert_eq!(uci.output.len(), 1);
+        assert!(uci.output[0].starts_with("info string invalid value "));
     }
 
     #[proptest]
@@ -708,6 +1487,124 @@ mod tests {
         let o = uci.options.clone();
         assert_eq!(block_on(uci.run()), Ok(()));
         assert_eq!(uci.options, o);
+        assert_eq!(uci.output.len(), 1);
+        assert!(uci.output[0].starts_with("info string invalid value "));
+    }
+
+    #[proptest]
+    fn handles_option_multipv(
+        #[any(StaticStream::new([format!("setoption name MultiPV value {}", #n)]))]
+        mut uci: MockUci,
+        n: MultiPv,
+    ) {
+        assert_eq!(block_on(uci.run()), Ok(()));
+        assert_eq!(uci.options.multipv, n);
+        assert!(uci.output.is_empty());
+    }
+
+    #[proptest]
+    fn handles_option_skill_level(
+        #[any(StaticStream::new([format!("setoption name Skill Level value {}", #s)]))]
+        mut uci: MockUci,
+        s: SkillLevel,
+    ) {
+        assert_eq!(block_on(uci.run()), Ok(()));
+        assert_eq!(uci.options.skill, s);
+        assert!(uci.output.is_empty());
+    }
+
+    #[proptest]
+    fn handles_option_uci_limit_strength(
+        #[any(StaticStream::new([
+            format!("setoption name UCI_Elo value {}", #e),
+            format!("setoption name UCI_LimitStrength value {}", #b),
+        ]))]
+        mut uci: MockUci,
+        e: Elo,
+        b: bool,
+    ) {
+        assert_eq!(block_on(uci.run()), Ok(()));
+        assert_eq!(uci.options.elo, e);
+        assert_eq!(uci.options.limit_strength, b);
+        assert!(uci.output.is_empty());
+    }
+
+    #[proptest]
+    fn handles_option_move_overhead(
+        #[any(StaticStream::new([format!("setoption name Move Overhead value {}", #t)]))]
+        mut uci: MockUci,
+        t: MoveOverhead,
+    ) {
+        assert_eq!(block_on(uci.run()), Ok(()));
+        assert_eq!(uci.options.move_overhead, t);
+        assert!(uci.output.is_empty());
+    }
+
+    #[proptest]
+    fn handles_option_minimum_thinking_time(
+        #[any(StaticStream::new([format!("setoption name Minimum Thinking Time value {}", #t)]))]
+        mut uci: MockUci,
+        t: MinThinkingTime,
+    ) {
+        assert_eq!(block_on(uci.run()), Ok(()));
+        assert_eq!(uci.options.min_thinking_time, t);
+        assert!(uci.output.is_empty());
+    }
+
+    #[proptest]
+    fn ignores_invalid_multipv(
+        #[any(StaticStream::new([format!("setoption name MultiPV value {}", #_s)]))]
+        mut uci: MockUci,
+        #[filter(#_s.trim().parse::<MultiPv>().is_err())] _s: String,
+    ) {
+        let o = uci.options.clone();
+        assert_eq!(block_on(uci.run()), Ok(()));
+        assert_eq!(uci.options, o);
+        assert_eq!(uci.output.len(), 1);
+        assert!(uci.output[0].starts_with("info string invalid value "));
+    }
+
+    #[proptest]
+    fn handles_option_clear_hash(
+        #[any(StaticStream::new(["setoption name Clear Hash"]))] mut uci: MockUci,
+    ) {
+        let o = uci.options.clone();
+        assert_eq!(block_on(uci.run()), Ok(()));
+        assert_eq!(uci.options, o);
+        assert!(uci.output.is_empty());
+    }
+
+    #[proptest]
+    fn handles_option_ponder(
+        #[any(StaticStream::new([format!("setoption name Ponder value {}", #_b)]))]
+        mut uci: MockUci,
+        _b: bool,
+    ) {
+        let o = uci.options.clone();
+        assert_eq!(block_on(uci.run()), Ok(()));
+        assert_eq!(uci.options, o);
+        assert!(uci.output.is_empty());
+    }
+
+    #[proptest]
+    fn handles_option_uci_chess960(
+        #[any(StaticStream::new([format!("setoption name UCI_Chess960 value {}", #b)]))]
+        mut uci: MockUci,
+        b: bool,
+    ) {
+        assert_eq!(block_on(uci.run()), Ok(()));
+        assert_eq!(uci.chess960, b);
+        assert!(uci.output.is_empty());
+    }
+
+    #[proptest]
+    fn handles_option_uci_show_wdl(
+        #[any(StaticStream::new([format!("setoption name UCI_ShowWDL value {}", #b)]))]
+        mut uci: MockUci,
+        b: bool,
+    ) {
+        assert_eq!(block_on(uci.run()), Ok(()));
+        assert_eq!(uci.show_wdl, b);
         assert!(uci.output.is_empty());
     }
 
@@ -717,6 +1614,66 @@ mod tests {
         #[strategy("[^[:ascii:]]*")] _s: String,
     ) {
         assert_eq!(block_on(uci.run()), Ok(()));
+        assert_eq!(uci.output.len(), usize::from(!_s.is_empty()));
+        assert!(
+            uci.output
+                .iter()
+                .all(|l| l.starts_with("info string failed to parse "))
+        );
+    }
+
+    #[proptest]
+    fn reports_invalid_fen_field(
+        #[any(StaticStream::new(["position fen 4k3/8/8/8/8/8/8/4K3 x - - 0 1"]))] mut uci: MockUci,
+    ) {
+        let pos = uci.position.clone();
+        assert_eq!(block_on(uci.run()), Ok(()));
+        assert_eq!(uci.position, pos);
+        assert_eq!(
+            uci.output,
+            [
+                "info string invalid fen `4k3/8/8/8/8/8/8/4K3 x - - 0 1`, failed to parse side to move"
+            ]
+        );
+    }
+
+    #[proptest]
+    fn reports_unknown_option(
+        #[any(StaticStream::new(["setoption name Contempt value 10"]))] mut uci: MockUci,
+    ) {
+        let o = uci.options.clone();
+        assert_eq!(block_on(uci.run()), Ok(()));
+        assert_eq!(uci.options, o);
+        assert_eq!(uci.output, ["info string unknown option `Contempt`"]);
+    }
+
+    #[proptest]
+    fn reports_unexpected_arguments(#[any(StaticStream::new(["eval now"]))] mut uci: MockUci) {
+        assert_eq!(block_on(uci.run()), Ok(()));
+        assert_eq!(uci.output, ["info string failed to parse `now`"]);
+    }
+
+    #[proptest]
+    fn handles_option_eval_file_with_embedded_network(
+        #[any(StaticStream::new(["setoption name EvalFile value <empty>"]))] mut uci: MockUci,
+    ) {
+        let pos = uci.position.clone();
+        assert_eq!(block_on(uci.run()), Ok(()));
+        assert_eq!(uci.position, pos);
         assert!(uci.output.is_empty());
     }
+
+    #[proptest]
+    fn reports_option_eval_file_with_missing_network(
+        #[any(StaticStream::new(["setoption name EvalFile value /nonexistent/nn.zst"]))]
+        mut uci: MockUci,
+    ) {
+        assert_eq!(block_on(uci.run()), Ok(()));
+        assert_eq!(uci.output.len(), 1);
+        assert!(
+            uci.output[0].starts_with(
+                "info string invalid value `/nonexistent/nn.zst` for option `EvalFile`"
+            )
+        );
+    }
 }
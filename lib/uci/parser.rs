@@ -36,8 +36,8 @@ where
 }
 
 #[allow(clippy::type_complexity)]
-pub fn gather<'s, A, B, C, D, E, F, G, H, I, J>(
-    inner: (A, B, C, D, E, F, G, H, I, J),
+pub fn gather<'s, A, B, C, D, E, F, G, H, I, J, K, L>(
+    inner: (A, B, C, D, E, F, G, H, I, J, K, L),
 ) -> impl Parser<
     &'s str,
     Output = (
@@ -51,6 +51,8 @@ pub fn gather<'s, A, B, C, D, E, F, G, H, I, J>(
         Option<<H as Parser<&'s str>>::Output>,
         Option<<I as Parser<&'s str>>::Output>,
         Option<<J as Parser<&'s str>>::Output>,
+        Option<<K as Parser<&'s str>>::Output>,
+        Option<<L as Parser<&'s str>>::Output>,
     ),
     Error = Error<&'s str>,
 >
@@ -65,11 +67,16 @@ where
     H: Parser<&'s str, Error = Error<&'s str>>,
     I: Parser<&'s str, Error = Error<&'s str>>,
     J: Parser<&'s str, Error = Error<&'s str>>,
+    K: Parser<&'s str, Error = Error<&'s str>>,
+    L: Parser<&'s str, Error = Error<&'s str>>,
 {
-    let (mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h, mut i, mut j) = inner;
+    let (mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h, mut i, mut j, mut k, mut l) =
+        inner;
 
     move |input: &'s str| {
-        let mut output = (None, None, None, None, None, None, None, None, None, None);
+        let mut output = (
+            None, None, None, None, None, None, None, None, None, None, None, None,
+        );
 
         let a = |s| a.parse(s);
         let b = |s| b.parse(s);
@@ -81,6 +88,8 @@ where
         let h = |s| h.parse(s);
         let i = |s| i.parse(s);
         let j = |s| j.parse(s);
+        let k = |s| k.parse(s);
+        let l = |s| l.parse(s);
 
         let inner = alt((
             a.map(|o| output.0 = Some(o)),
@@ -93,6 +102,8 @@ where
             h.map(|o| output.7 = Some(o)),
             i.map(|o| output.8 = Some(o)),
             j.map(|o| output.9 = Some(o)),
+            k.map(|o| output.10 = Some(o)),
+            l.map(|o| output.11 = Some(o)),
         ));
 
         let (rest, _) = fold_many0(inner, || (), |_, _| ()).parse(input)?;
@@ -117,7 +128,9 @@ macro_rules! define_gather {
     };
 }
 
-define_gather!(gather9, gather, A, B, C, D, E, F, G, H, I);
+define_gather!(gather11, gather, A, B, C, D, E, F, G, H, I, J, K);
+define_gather!(gather10, gather11, A, B, C, D, E, F, G, H, I, J);
+define_gather!(gather9, gather10, A, B, C, D, E, F, G, H, I);
 define_gather!(gather8, gather9, A, B, C, D, E, F, G, H);
 define_gather!(gather7, gather8, A, B, C, D, E, F, G);
 define_gather!(gather6, gather7, A, B, C, D, E, F);
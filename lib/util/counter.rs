@@ -3,6 +3,7 @@ use std::sync::atomic::{AtomicU64, Ordering};
 /// A counter towards a limit.
 #[derive(Debug)]
 pub struct Counter {
+    limit: u64,
     remaining: AtomicU64,
 }
 
@@ -11,10 +12,17 @@ impl Counter {
     #[inline(always)]
     pub const fn new(limit: u64) -> Self {
         Counter {
+            limit,
             remaining: AtomicU64::new(limit),
         }
     }
 
+    /// The number of counts so far.
+    #[inline(always)]
+    pub fn counted(&self) -> u64 {
+        self.limit - self.get()
+    }
+
     /// The number of counts remaining.
     #[inline(always)]
     pub fn get(&self) -> u64 {
@@ -41,6 +49,16 @@ mod tests {
         assert_eq!(counter.count(), Some(c - 1));
     }
 
+    #[proptest]
+    fn counter_keeps_track_of_counts_so_far(c: u64, #[strategy(..=#c.min(100))] n: u64) {
+        let counter = Counter::new(c);
+        for _ in 0..n {
+            counter.count();
+        }
+
+        assert_eq!(counter.counted(), n);
+    }
+
     #[test]
     fn counter_stops_once_limit_is_reached() {
         let counter = Counter::new(0);
@@ -10,45 +10,81 @@ fn elapsed() -> Duration {
 }
 
 /// Tracks time towards a deadline.
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct Timer {
     spinner: AtomicU8,
-    deadline: Option<Duration>,
+    deadline: AtomicU64,
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Self::infinite()
+    }
 }
 
 impl Timer {
-    /// Constructs a timer that elapses after the given duration.
+    const NEVER: u64 = u64::MAX;
+
+    /// How close to the deadline the time remaining is always measured precisely.
+    const MARGIN: Duration = Duration::from_millis(10);
+
+    /// Constructs a timer that never elapses.
     #[inline(always)]
     pub const fn infinite() -> Self {
         Timer {
             spinner: AtomicU8::new(0),
-            deadline: None,
+            deadline: AtomicU64::new(Self::NEVER),
         }
     }
 
     /// Constructs a timer that elapses after the given duration.
     #[inline(always)]
     pub fn new(duration: Duration) -> Self {
-        Timer {
-            spinner: AtomicU8::new(255),
-            deadline: elapsed().checked_add(duration),
+        let timer = Timer::infinite();
+        timer.start(duration);
+        timer
+    }
+
+    /// Starts an infinite timer so that it elapses after the given duration.
+    ///
+    /// Has no effect if the timer has already been started.
+    #[inline(always)]
+    pub fn start(&self, duration: Duration) {
+        let deadline = match elapsed().checked_add(duration) {
+            Some(deadline) => (deadline.as_micros() as u64).min(Self::NEVER - 1),
+            None => Self::NEVER,
+        };
+
+        let (never, relaxed) = (Self::NEVER, Ordering::Relaxed);
+        if self
+            .deadline
+            .compare_exchange(never, deadline, relaxed, relaxed)
+            .is_ok()
+        {
+            self.spinner.store(255, Ordering::Relaxed);
         }
     }
 
     /// Returns the time remaining if any.
+    ///
+    /// The clock is only sampled every so often, except when close to the deadline.
     #[inline(always)]
     pub fn remaining(&self) -> Option<Duration> {
         static MICROS: AtomicU64 = AtomicU64::new(0);
 
-        match self.deadline {
-            None => Some(Duration::MAX),
-            Some(deadline) => {
-                if self.spinner.fetch_add(1, Ordering::Relaxed) == 255 {
+        match self.deadline.load(Ordering::Relaxed) {
+            Self::NEVER => Some(Duration::MAX),
+            deadline => {
+                let deadline = Duration::from_micros(deadline);
+                let micros = Duration::from_micros(MICROS.load(Ordering::Relaxed));
+                let cached = deadline.checked_sub(micros);
+                let spin = self.spinner.fetch_add(1, Ordering::Relaxed);
+                if spin != 255 && cached > Some(Self::MARGIN) {
+                    cached
+                } else {
                     let elapsed = elapsed();
                     MICROS.fetch_max(elapsed.as_micros() as _, Ordering::Relaxed);
                     deadline.checked_sub(elapsed)
-                } else {
-                    deadline.checked_sub(Duration::from_micros(MICROS.load(Ordering::Relaxed)))
                 }
             }
         }
@@ -73,6 +109,30 @@ mod tests {
         assert_eq!(timer.remaining(), None);
     }
 
+    #[test]
+    fn timer_elapses_once_started() {
+        let timer = Timer::infinite();
+        timer.start(Duration::ZERO);
+        sleep(Duration::from_millis(1));
+        assert_eq!(timer.remaining(), None);
+    }
+
+    #[test]
+    fn timer_measures_time_precisely_close_to_the_deadline() {
+        let timer = Timer::new(Timer::MARGIN / 2);
+        assert!(timer.remaining().is_some());
+        sleep(Timer::MARGIN);
+        assert_eq!(timer.remaining(), None);
+    }
+
+    #[test]
+    fn timer_can_only_be_started_once() {
+        let timer = Timer::new(Duration::ZERO);
+        timer.start(Duration::from_secs(1));
+        sleep(Duration::from_millis(1));
+        assert_eq!(timer.remaining(), None);
+    }
+
     #[test]
     fn timer_never_decreases_if_infinite() {
         let timer = Timer::infinite();